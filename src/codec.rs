@@ -0,0 +1,50 @@
+use base64::alphabet::{BCRYPT, STANDARD};
+use base64::engine::general_purpose::{GeneralPurpose, GeneralPurposeConfig};
+use base64::engine::DecodePaddingMode;
+
+// java.util.Base64.getEncoder() / getDecoder(), java's decoder does not require padding
+pub(crate) const BASE64: GeneralPurpose = GeneralPurpose::new(&STANDARD, GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent));
+
+// java.util.Base64.getEncoder().withoutPadding()
+pub(crate) const BASE64_WITHOUT_PADDING: GeneralPurpose = GeneralPurpose::new(
+    &STANDARD,
+    GeneralPurposeConfig::new()
+        .with_encode_padding(false)
+        .with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+// radix-64 of the bcrypt salt and hash, the unused bits of the last character are ignored like jBCrypt does
+pub(crate) const BCRYPT_BASE64: GeneralPurpose = GeneralPurpose::new(
+    &BCRYPT,
+    GeneralPurposeConfig::new()
+        .with_encode_padding(false)
+        .with_decode_padding_mode(DecodePaddingMode::RequireNone)
+        .with_decode_allow_trailing_bits(true),
+);
+
+#[cfg(test)]
+mod tests {
+    use super::{BASE64, BASE64_WITHOUT_PADDING, BCRYPT_BASE64};
+    use base64::Engine;
+
+    #[test]
+    fn decodes_with_and_without_padding() {
+        assert_eq!(BASE64.decode("c29tZXNhbHQ=").unwrap(), b"somesalt");
+        assert_eq!(BASE64.decode("c29tZXNhbHQ").unwrap(), b"somesalt");
+        assert_eq!(BASE64_WITHOUT_PADDING.decode("c29tZXNhbHQ").unwrap(), b"somesalt");
+        assert_eq!(BASE64_WITHOUT_PADDING.decode("c29tZXNhbHQ=").unwrap(), b"somesalt");
+    }
+
+    #[test]
+    fn encodes_padding_only_when_wanted() {
+        assert_eq!(BASE64.encode(b"somesalt"), "c29tZXNhbHQ=");
+        assert_eq!(BASE64_WITHOUT_PADDING.encode(b"somesalt"), "c29tZXNhbHQ");
+    }
+
+    #[test]
+    fn decodes_bcrypt_salt_and_hash() {
+        // salt and hash of $2a$10$dXJ3SW6G7P50lGmMkkmwe.20cQQubK3.HZWzG3YB1tlRy.fqvM/BG
+        assert_eq!(BCRYPT_BASE64.decode("dXJ3SW6G7P50lGmMkkmwe.").unwrap().len(), 16);
+        assert_eq!(BCRYPT_BASE64.decode("20cQQubK3.HZWzG3YB1tlRy.fqvM/BG").unwrap().len(), 23);
+    }
+}
@@ -1,17 +1,16 @@
-/*
+pub mod any;
 pub mod argon2;
-*/
 pub mod bcrypt;
 pub mod delegating;
-/*
 pub mod ldap;
 pub mod md;
-*/
 pub mod md4;
 pub mod md5;
 pub mod noop;
-/*
+pub mod onion;
 pub mod pbkdf2;
+pub mod pepper;
 pub mod scrypt;
+pub mod sha1;
+pub mod sha256;
 pub mod standard;
- */
@@ -0,0 +1,277 @@
+use crate::encoder::argon2::Argon2PasswordEncoder;
+use crate::encoder::bcrypt::{BCryptPasswordEncoder, SaltSource};
+use crate::encoder::delegating::DelegatingPasswordEncoder;
+use crate::encoder::ldap::LdapShaPasswordEncoder;
+use crate::encoder::md4::Md4PasswordEncoder;
+use crate::encoder::md5::Md5PasswordEncoder;
+use crate::encoder::noop::NoOpPasswordEncoder;
+use crate::encoder::onion::{BCryptMd4PasswordEncoder, BCryptMd5PasswordEncoder};
+use crate::encoder::pbkdf2::Pbkdf2PasswordEncoder;
+use crate::encoder::scrypt::SCryptPasswordEncoder;
+use crate::encoder::sha1::Sha1PasswordEncoder;
+use crate::encoder::sha256::Sha256PasswordEncoder;
+use crate::encoder::standard::StandardPasswordEncoder;
+use crate::error::PasswordEncoderError;
+use crate::{Encoder, PasswordEncoder};
+use rand_core::OsRng;
+use std::fmt;
+
+/// Any of the encoders, dispatched by match instead of boxing a trait object
+///
+/// The salt source of the bcrypt encoder is a type parameter, like for `BCryptPasswordEncoder` itself.
+pub enum AnyEncoder<S = OsRng> {
+    Argon2(Argon2PasswordEncoder),
+    BCrypt(BCryptPasswordEncoder<S>),
+    BCryptMd4(BCryptMd4PasswordEncoder),
+    BCryptMd5(BCryptMd5PasswordEncoder),
+    Ldap(LdapShaPasswordEncoder),
+    Md4(Md4PasswordEncoder),
+    Md5(Md5PasswordEncoder),
+    NoOp(NoOpPasswordEncoder),
+    Pbkdf2(Pbkdf2PasswordEncoder),
+    SCrypt(SCryptPasswordEncoder),
+    Sha1(Sha1PasswordEncoder),
+    Sha256(Sha256PasswordEncoder),
+    Standard(StandardPasswordEncoder),
+    /// Still looks up boxed encoders by id, only the other variants avoid the trait objects
+    Delegating(DelegatingPasswordEncoder),
+}
+
+impl From<Encoder> for AnyEncoder {
+    /// Same configuration as `Encoder::create_password_encoder`
+    fn from(encoder: Encoder) -> AnyEncoder {
+        match encoder {
+            Encoder::ARGON2 => AnyEncoder::Argon2(Argon2PasswordEncoder::defaults_for_spring_security_v5_2()),
+            Encoder::ARGON2V5_8 => AnyEncoder::Argon2(Argon2PasswordEncoder::defaults_for_spring_security_v5_8()),
+            Encoder::BCRYPT => AnyEncoder::BCrypt(Default::default()),
+            Encoder::BCRYPTMD4 => AnyEncoder::BCryptMd4(Default::default()),
+            Encoder::BCRYPTMD5 => AnyEncoder::BCryptMd5(Default::default()),
+            Encoder::LDAP => AnyEncoder::Ldap(Default::default()),
+            Encoder::MD4 => AnyEncoder::Md4(Default::default()),
+            Encoder::MD5 => AnyEncoder::Md5(Default::default()),
+            Encoder::NOOP => AnyEncoder::NoOp(Default::default()),
+            Encoder::PBKDF2 => AnyEncoder::Pbkdf2(Pbkdf2PasswordEncoder::defaults_for_spring_security_v5_5()),
+            Encoder::PBKDF2V5_8 => AnyEncoder::Pbkdf2(Pbkdf2PasswordEncoder::defaults_for_spring_security_v5_8()),
+            Encoder::SCRYPT => AnyEncoder::SCrypt(SCryptPasswordEncoder::defaults_for_spring_security_v4_1()),
+            Encoder::SCRYPTV5_8 => AnyEncoder::SCrypt(SCryptPasswordEncoder::defaults_for_spring_security_v5_8()),
+            Encoder::SHA1 => AnyEncoder::Sha1(Default::default()),
+            Encoder::SHA256 => AnyEncoder::Sha256(Default::default()),
+            Encoder::STANDARD => AnyEncoder::Standard(Default::default()),
+            Encoder::DELEGATING => AnyEncoder::Delegating(Default::default()),
+        }
+    }
+}
+
+impl From<Argon2PasswordEncoder> for AnyEncoder {
+    fn from(encoder: Argon2PasswordEncoder) -> AnyEncoder {
+        AnyEncoder::Argon2(encoder)
+    }
+}
+
+impl<S> From<BCryptPasswordEncoder<S>> for AnyEncoder<S> {
+    fn from(encoder: BCryptPasswordEncoder<S>) -> AnyEncoder<S> {
+        AnyEncoder::BCrypt(encoder)
+    }
+}
+
+impl From<BCryptMd4PasswordEncoder> for AnyEncoder {
+    fn from(encoder: BCryptMd4PasswordEncoder) -> AnyEncoder {
+        AnyEncoder::BCryptMd4(encoder)
+    }
+}
+
+impl From<BCryptMd5PasswordEncoder> for AnyEncoder {
+    fn from(encoder: BCryptMd5PasswordEncoder) -> AnyEncoder {
+        AnyEncoder::BCryptMd5(encoder)
+    }
+}
+
+impl From<LdapShaPasswordEncoder> for AnyEncoder {
+    fn from(encoder: LdapShaPasswordEncoder) -> AnyEncoder {
+        AnyEncoder::Ldap(encoder)
+    }
+}
+
+impl From<Md4PasswordEncoder> for AnyEncoder {
+    fn from(encoder: Md4PasswordEncoder) -> AnyEncoder {
+        AnyEncoder::Md4(encoder)
+    }
+}
+
+impl From<Md5PasswordEncoder> for AnyEncoder {
+    fn from(encoder: Md5PasswordEncoder) -> AnyEncoder {
+        AnyEncoder::Md5(encoder)
+    }
+}
+
+impl From<NoOpPasswordEncoder> for AnyEncoder {
+    fn from(encoder: NoOpPasswordEncoder) -> AnyEncoder {
+        AnyEncoder::NoOp(encoder)
+    }
+}
+
+impl From<Pbkdf2PasswordEncoder> for AnyEncoder {
+    fn from(encoder: Pbkdf2PasswordEncoder) -> AnyEncoder {
+        AnyEncoder::Pbkdf2(encoder)
+    }
+}
+
+impl From<SCryptPasswordEncoder> for AnyEncoder {
+    fn from(encoder: SCryptPasswordEncoder) -> AnyEncoder {
+        AnyEncoder::SCrypt(encoder)
+    }
+}
+
+impl From<Sha1PasswordEncoder> for AnyEncoder {
+    fn from(encoder: Sha1PasswordEncoder) -> AnyEncoder {
+        AnyEncoder::Sha1(encoder)
+    }
+}
+
+impl From<Sha256PasswordEncoder> for AnyEncoder {
+    fn from(encoder: Sha256PasswordEncoder) -> AnyEncoder {
+        AnyEncoder::Sha256(encoder)
+    }
+}
+
+impl From<StandardPasswordEncoder> for AnyEncoder {
+    fn from(encoder: StandardPasswordEncoder) -> AnyEncoder {
+        AnyEncoder::Standard(encoder)
+    }
+}
+
+impl From<DelegatingPasswordEncoder> for AnyEncoder {
+    fn from(encoder: DelegatingPasswordEncoder) -> AnyEncoder {
+        AnyEncoder::Delegating(encoder)
+    }
+}
+
+// calls the same method on whichever encoder is held
+macro_rules! dispatch {
+    ($any_encoder:expr, $encoder:ident => $call:expr) => {
+        match $any_encoder {
+            AnyEncoder::Argon2($encoder) => $call,
+            AnyEncoder::BCrypt($encoder) => $call,
+            AnyEncoder::BCryptMd4($encoder) => $call,
+            AnyEncoder::BCryptMd5($encoder) => $call,
+            AnyEncoder::Ldap($encoder) => $call,
+            AnyEncoder::Md4($encoder) => $call,
+            AnyEncoder::Md5($encoder) => $call,
+            AnyEncoder::NoOp($encoder) => $call,
+            AnyEncoder::Pbkdf2($encoder) => $call,
+            AnyEncoder::SCrypt($encoder) => $call,
+            AnyEncoder::Sha1($encoder) => $call,
+            AnyEncoder::Sha256($encoder) => $call,
+            AnyEncoder::Standard($encoder) => $call,
+            AnyEncoder::Delegating($encoder) => $call,
+        }
+    };
+}
+
+impl<S> fmt::Debug for AnyEncoder<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        dispatch!(self, encoder => fmt::Debug::fmt(encoder, f))
+    }
+}
+
+impl<S: SaltSource> PasswordEncoder for AnyEncoder<S> {
+    fn matches_spring_security_hash(&self, unencoded_password: &str, encoded_password: &str) -> bool {
+        dispatch!(self, encoder => encoder.matches_spring_security_hash(unencoded_password, encoded_password))
+    }
+
+    fn encode_spring_security_hash(&self, unencoded_password: &str) -> Option<String> {
+        dispatch!(self, encoder => encoder.encode_spring_security_hash(unencoded_password))
+    }
+
+    fn try_matches_spring_security_hash_bytes(&self, unencoded_password: &[u8], encoded_password: &str) -> Result<bool, PasswordEncoderError> {
+        dispatch!(self, encoder => encoder.try_matches_spring_security_hash_bytes(unencoded_password, encoded_password))
+    }
+
+    fn try_encode_spring_security_hash_bytes(&self, unencoded_password: &[u8]) -> Result<String, PasswordEncoderError> {
+        dispatch!(self, encoder => encoder.try_encode_spring_security_hash_bytes(unencoded_password))
+    }
+
+    fn try_upgrade_encoding(&self, encoded_password: &str) -> Result<bool, PasswordEncoderError> {
+        dispatch!(self, encoder => encoder.try_upgrade_encoding(encoded_password))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AnyEncoder;
+    use crate::encoder::bcrypt::{BCryptPasswordEncoder, BCryptVersion};
+    use crate::encoder::md5::Md5PasswordEncoder;
+    use crate::error::PasswordEncoderError;
+    use crate::{Encoder, PasswordEncoder};
+    use rand_core::{CryptoRng, RngCore};
+    use std::num::NonZeroU32;
+
+    #[test]
+    fn matches_hash_of_held_encoder() {
+        let encoder = AnyEncoder::from(Encoder::MD5);
+
+        assert!(encoder.matches_spring_security_hash("password", "{thisissalt}2a4e7104c2780098f50ed5a84bb2323d"));
+        assert!(!encoder.matches_spring_security_hash("wrongpassword", "{thisissalt}2a4e7104c2780098f50ed5a84bb2323d"));
+        assert_eq!(
+            encoder.try_matches_spring_security_hash("password", "{thisissalt}not hex"),
+            Err(PasswordEncoderError::MalformedHash)
+        );
+    }
+
+    #[test]
+    fn encodes_with_configured_encoder() {
+        let encoder = AnyEncoder::from(BCryptPasswordEncoder::new(BCryptVersion::TwoB, 4).unwrap());
+
+        let encoded_password = encoder.encode_spring_security_hash("password").unwrap();
+
+        assert!(encoded_password.starts_with("$2b$04$"));
+        assert!(encoder.matches_spring_security_hash("password", &encoded_password));
+        assert!(!encoder.upgrade_encoding(&encoded_password));
+    }
+
+    #[test]
+    fn encodes_with_random_of_bcrypt_encoder() {
+        struct FailingRng;
+
+        impl RngCore for FailingRng {
+            fn next_u32(&mut self) -> u32 {
+                0
+            }
+
+            fn next_u64(&mut self) -> u64 {
+                0
+            }
+
+            fn fill_bytes(&mut self, _dest: &mut [u8]) {}
+
+            fn try_fill_bytes(&mut self, _dest: &mut [u8]) -> Result<(), rand_core::Error> {
+                Err(rand_core::Error::from(NonZeroU32::new(rand_core::Error::CUSTOM_START).unwrap()))
+            }
+        }
+
+        impl CryptoRng for FailingRng {}
+
+        let encoder = AnyEncoder::from(BCryptPasswordEncoder::with_random(BCryptVersion::TwoA, 4, FailingRng).unwrap());
+
+        assert_eq!(encoder.try_encode_spring_security_hash("password"), Err(PasswordEncoderError::RandomSourceFailure));
+        assert!(encoder.matches_spring_security_hash("U*U", "$2a$05$CCCCCCCCCCCCCCCCCCCCC.E5YPO9kmyuRGyh0XouQYb4YMJKvyOeW"));
+    }
+
+    #[test]
+    fn upgrades_like_held_encoder() {
+        let encoder = AnyEncoder::from(Encoder::DELEGATING);
+
+        assert!(encoder.upgrade_encoding("{MD5}{thisissalt}2a4e7104c2780098f50ed5a84bb2323d"));
+        assert!(!AnyEncoder::from(Md5PasswordEncoder::default()).upgrade_encoding("{thisissalt}2a4e7104c2780098f50ed5a84bb2323d"));
+    }
+
+    #[test]
+    fn every_encoder_matches_its_own_hashes() {
+        for encoder in Encoder::ALL {
+            let any_encoder = AnyEncoder::from(encoder);
+            let encoded_password = any_encoder.encode_spring_security_hash("password").unwrap();
+
+            assert!(any_encoder.matches_spring_security_hash("password", &encoded_password), "{}", encoder);
+        }
+    }
+}
@@ -1,2 +1,407 @@
-#[derive(Default)]
-pub struct Argon2PasswordEncoder {}
+use crate::codec::BASE64_WITHOUT_PADDING;
+use crate::error::PasswordEncoderError;
+use crate::keygen::secure_random_bytes;
+use crate::util::constant_time_equals;
+use crate::PasswordEncoder;
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::Engine;
+use zeroize::Zeroizing;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Argon2PasswordEncoder {
+    salt_length: usize,
+    hash_length: usize,
+    parallelism: u32,
+    memory: u32,
+    iterations: u32,
+}
+
+impl Default for Argon2PasswordEncoder {
+    fn default() -> Argon2PasswordEncoder {
+        Argon2PasswordEncoder::defaults_for_spring_security_v5_2()
+    }
+}
+
+impl Argon2PasswordEncoder {
+    /// Registered as `argon2` by the delegating encoder
+    pub fn defaults_for_spring_security_v5_2() -> Argon2PasswordEncoder {
+        // https://github.com/spring-projects/spring-security/blob/0e3ee8990299436223c3037afb096f454f17ef5e/crypto/src/main/java/org/springframework/security/crypto/argon2/Argon2PasswordEncoder.java#L65-L73
+        Argon2PasswordEncoder::new(16, 32, 1, 1 << 12, 3)
+    }
+
+    /// Registered as `argon2@SpringSecurity_v5_8` by the delegating encoder
+    pub fn defaults_for_spring_security_v5_8() -> Argon2PasswordEncoder {
+        // https://github.com/spring-projects/spring-security/blob/0e3ee8990299436223c3037afb096f454f17ef5e/crypto/src/main/java/org/springframework/security/crypto/argon2/Argon2PasswordEncoder.java
+        Argon2PasswordEncoder::new(16, 32, 1, 1 << 14, 2)
+    }
+
+    /// Same parameter order as the Spring Security constructor, memory is given in KiB
+    pub fn new(salt_length: usize, hash_length: usize, parallelism: u32, memory: u32, iterations: u32) -> Argon2PasswordEncoder {
+        Argon2PasswordEncoder {
+            salt_length,
+            hash_length,
+            parallelism,
+            memory,
+            iterations,
+        }
+    }
+}
+
+// https://github.com/spring-projects/spring-security/blob/0e3ee8990299436223c3037afb096f454f17ef5e/crypto/src/main/java/org/springframework/security/crypto/argon2/Argon2EncodingUtils.java
+#[derive(Debug, PartialEq)]
+pub(crate) struct Argon2Hash {
+    pub(crate) algorithm: Algorithm,
+    pub(crate) version: Version,
+    pub(crate) memory: u32,
+    pub(crate) iterations: u32,
+    pub(crate) parallelism: u32,
+    pub(crate) salt: Vec<u8>,
+    pub(crate) hash: Vec<u8>,
+}
+
+impl Argon2Hash {
+    pub(crate) fn decode(encoded_hash: &str) -> Option<Argon2Hash> {
+        let parts: Vec<&str> = encoded_hash.split('$').collect();
+        if parts.len() < 4 {
+            return None;
+        }
+
+        let mut current_part = 1;
+        let algorithm = match parts[current_part] {
+            "argon2d" => Algorithm::Argon2d,
+            "argon2i" => Algorithm::Argon2i,
+            "argon2id" => Algorithm::Argon2id,
+            _ => return None,
+        };
+        current_part += 1;
+
+        // version is optional, bouncy castle defaults to 0x13 when missing
+        let mut version = Version::V0x13;
+        if let Some(found_version) = parts[current_part].strip_prefix("v=") {
+            version = match found_version.parse::<u32>().ok()? {
+                0x10 => Version::V0x10,
+                0x13 => Version::V0x13,
+                _ => return None,
+            };
+            current_part += 1;
+        }
+
+        let performance_params: Vec<&str> = parts.get(current_part)?.split(',').collect();
+        if performance_params.len() != 3 {
+            return None;
+        }
+        let memory = performance_params[0].strip_prefix("m=")?.parse::<u32>().ok()?;
+        let iterations = performance_params[1].strip_prefix("t=")?.parse::<u32>().ok()?;
+        let parallelism = performance_params[2].strip_prefix("p=")?.parse::<u32>().ok()?;
+        current_part += 1;
+
+        let salt = BASE64_WITHOUT_PADDING.decode(parts.get(current_part)?).ok()?;
+        current_part += 1;
+        let hash = BASE64_WITHOUT_PADDING.decode(parts.get(current_part)?).ok()?;
+
+        Some(Argon2Hash {
+            algorithm,
+            version,
+            memory,
+            iterations,
+            parallelism,
+            salt,
+            hash,
+        })
+    }
+
+    // fills the given buffer with the hash of the password, using the parameters and salt of this hash
+    fn hash_password_into(&self, unencoded_password: &[u8], output: &mut [u8]) -> Result<(), PasswordEncoderError> {
+        let params = Params::new(self.memory, self.iterations, self.parallelism, Some(output.len())).map_err(|_| PasswordEncoderError::InvalidParameters)?;
+        Argon2::new(self.algorithm, self.version, params)
+            .hash_password_into(unencoded_password, &self.salt, output)
+            .map_err(|_| PasswordEncoderError::InvalidParameters)
+    }
+
+    pub(crate) fn encode(&self) -> String {
+        let type_name = match self.algorithm {
+            Algorithm::Argon2d => "argon2d",
+            Algorithm::Argon2i => "argon2i",
+            Algorithm::Argon2id => "argon2id",
+        };
+        format!(
+            "${}$v={}$m={},t={},p={}${}${}",
+            type_name,
+            self.version as u32,
+            self.memory,
+            self.iterations,
+            self.parallelism,
+            BASE64_WITHOUT_PADDING.encode(&self.salt),
+            BASE64_WITHOUT_PADDING.encode(&self.hash)
+        )
+    }
+}
+
+impl PasswordEncoder for Argon2PasswordEncoder {
+    fn matches_spring_security_hash(&self, unencoded_password: &str, encoded_password: &str) -> bool {
+        self.try_matches_spring_security_hash(unencoded_password, encoded_password).unwrap_or(false)
+    }
+
+    fn encode_spring_security_hash(&self, unencoded_password: &str) -> Option<String> {
+        self.try_encode_spring_security_hash(unencoded_password).ok()
+    }
+
+    fn try_matches_spring_security_hash_bytes(&self, unencoded_password: &[u8], encoded_password: &str) -> Result<bool, PasswordEncoderError> {
+        let decoded = Argon2Hash::decode(encoded_password).ok_or(PasswordEncoderError::MalformedHash)?;
+
+        let mut hash = Zeroizing::new(vec![0u8; decoded.hash.len()]);
+        decoded.hash_password_into(unencoded_password, &mut hash)?;
+        Ok(constant_time_equals(&decoded.hash, &hash))
+    }
+
+    fn try_encode_spring_security_hash_bytes(&self, unencoded_password: &[u8]) -> Result<String, PasswordEncoderError> {
+        // spring security always encodes using argon2id and the current version
+        let mut encoded = Argon2Hash {
+            algorithm: Algorithm::Argon2id,
+            version: Version::V0x13,
+            memory: self.memory,
+            iterations: self.iterations,
+            parallelism: self.parallelism,
+            salt: secure_random_bytes(self.salt_length)?,
+            hash: vec![],
+        };
+        let mut hash = vec![0u8; self.hash_length];
+        encoded.hash_password_into(unencoded_password, &mut hash)?;
+        encoded.hash = hash;
+
+        Ok(encoded.encode())
+    }
+
+    /// Upgrades hashes with less memory or iterations than configured
+    fn try_upgrade_encoding(&self, encoded_password: &str) -> Result<bool, PasswordEncoderError> {
+        // https://github.com/spring-projects/spring-security/blob/0e3ee8990299436223c3037afb096f454f17ef5e/crypto/src/main/java/org/springframework/security/crypto/argon2/Argon2PasswordEncoder.java
+        if encoded_password.is_empty() {
+            return Ok(false);
+        }
+        let decoded = Argon2Hash::decode(encoded_password).ok_or(PasswordEncoderError::MalformedHash)?;
+        Ok(decoded.memory < self.memory || decoded.iterations < self.iterations)
+    }
+}
+
+#[cfg(test)]
+mod decoding_tests {
+    use super::Argon2Hash;
+    use argon2::{Algorithm, Version};
+
+    #[test]
+    fn decodes_argon2id() {
+        let decoded = Argon2Hash::decode("$argon2id$v=19$m=4096,t=3,p=1$c29tZXNhbHQ$cGFzc3dvcmQ").unwrap();
+
+        assert_eq!(decoded.algorithm, Algorithm::Argon2id);
+        assert_eq!(decoded.version, Version::V0x13);
+        assert_eq!(decoded.memory, 4096);
+        assert_eq!(decoded.iterations, 3);
+        assert_eq!(decoded.parallelism, 1);
+        assert_eq!(decoded.salt, b"somesalt");
+        assert_eq!(decoded.hash, b"password");
+    }
+
+    #[test]
+    fn decodes_legacy_version() {
+        let decoded = Argon2Hash::decode("$argon2i$v=16$m=65536,t=2,p=4$c29tZXNhbHQ$cGFzc3dvcmQ").unwrap();
+
+        assert_eq!(decoded.algorithm, Algorithm::Argon2i);
+        assert_eq!(decoded.version, Version::V0x10);
+    }
+
+    #[test]
+    fn decodes_without_version() {
+        let decoded = Argon2Hash::decode("$argon2d$m=65536,t=2,p=4$c29tZXNhbHQ$cGFzc3dvcmQ").unwrap();
+
+        assert_eq!(decoded.algorithm, Algorithm::Argon2d);
+        assert_eq!(decoded.version, Version::V0x13);
+        assert_eq!(decoded.memory, 65536);
+    }
+
+    #[test]
+    fn fails_on_unknown_type() {
+        assert!(Argon2Hash::decode("$argon2x$v=19$m=4096,t=3,p=1$c29tZXNhbHQ$cGFzc3dvcmQ").is_none());
+    }
+
+    #[test]
+    fn fails_on_unknown_version() {
+        assert!(Argon2Hash::decode("$argon2id$v=18$m=4096,t=3,p=1$c29tZXNhbHQ$cGFzc3dvcmQ").is_none());
+    }
+
+    #[test]
+    fn fails_on_missing_performance_params() {
+        assert!(Argon2Hash::decode("$argon2id$v=19$m=4096,t=3$c29tZXNhbHQ$cGFzc3dvcmQ").is_none());
+        assert!(Argon2Hash::decode("$argon2id$v=19$t=3,m=4096,p=1$c29tZXNhbHQ$cGFzc3dvcmQ").is_none());
+    }
+
+    #[test]
+    fn fails_on_missing_hash() {
+        assert!(Argon2Hash::decode("$argon2id$v=19$m=4096,t=3,p=1$c29tZXNhbHQ").is_none());
+    }
+
+    #[test]
+    fn fails_on_bcrypt_hash() {
+        assert!(Argon2Hash::decode("$2a$10$dXJ3SW6G7P50lGmMkkmwe.20cQQubK3.HZWzG3YB1tlRy.fqvM/BG").is_none());
+    }
+
+    #[test]
+    fn encodes_back_to_same_string() {
+        let encoded_hash = "$argon2i$v=19$m=65536,t=2,p=4$c29tZXNhbHQ$RdescudvJCsgt3ub+b+dWRWJTmaaJObG";
+
+        assert_eq!(Argon2Hash::decode(encoded_hash).unwrap().encode(), encoded_hash);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Argon2PasswordEncoder, PasswordEncoder};
+    use crate::error::PasswordEncoderError;
+
+    #[test]
+    fn matches_reference_implementation_hash() {
+        // taken from https://github.com/P-H-C/phc-winner-argon2#command-line-utility
+        let encoder: Argon2PasswordEncoder = Default::default();
+
+        let unencoded_password = String::from("password");
+        let encoded_password = String::from("$argon2i$v=19$m=65536,t=2,p=4$c29tZXNhbHQ$RdescudvJCsgt3ub+b+dWRWJTmaaJObG");
+
+        assert!(encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+    }
+
+    #[test]
+    fn matches_spring_security_hash_with_predictable_salt() {
+        // https://github.com/spring-projects/spring-security/blob/0e3ee8990299436223c3037afb096f454f17ef5e/crypto/src/test/java/org/springframework/security/crypto/argon2/Argon2PasswordEncoderTests.java
+        let encoder: Argon2PasswordEncoder = Default::default();
+
+        let unencoded_password = String::from("sometestpassword");
+        let encoded_password = String::from("$argon2id$v=19$m=4096,t=3,p=1$QUFBQUFBQUFBQUFBQUFBQQ$hmmTNyJlwbb6HAvFoHFWF+u03fdb0F2qA+39oPlcAqo");
+
+        assert!(encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+    }
+
+    #[test]
+    fn matches_legacy_version() {
+        // taken from https://github.com/P-H-C/phc-winner-argon2/blob/master/src/test.c
+        let encoder: Argon2PasswordEncoder = Default::default();
+
+        let unencoded_password = String::from("password");
+        let encoded_password = String::from("$argon2i$v=16$m=65536,t=2,p=1$c29tZXNhbHQ$9sTbSlTio3Biev89thdrlKKiCaYsjjYVJxGAL3swxpQ");
+
+        assert!(encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+    }
+
+    #[test]
+    fn no_match_for_wrong_password() {
+        let encoder: Argon2PasswordEncoder = Default::default();
+
+        let unencoded_password = String::from("wrongpassword");
+        let encoded_password = String::from("$argon2i$v=19$m=65536,t=2,p=4$c29tZXNhbHQ$RdescudvJCsgt3ub+b+dWRWJTmaaJObG");
+
+        assert!(!encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+    }
+
+    #[test]
+    fn no_match_for_malformed_hash() {
+        let encoder: Argon2PasswordEncoder = Default::default();
+
+        let unencoded_password = String::from("password");
+        let encoded_password = String::from("$2a$10$dXJ3SW6G7P50lGmMkkmwe.20cQQubK3.HZWzG3YB1tlRy.fqvM/BG");
+
+        assert!(!encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+        assert_eq!(
+            encoder.try_matches_spring_security_hash(&unencoded_password, &encoded_password),
+            Err(PasswordEncoderError::MalformedHash)
+        );
+    }
+
+    #[test]
+    fn error_for_invalid_params() {
+        let encoder: Argon2PasswordEncoder = Default::default();
+
+        // memory below 8 times the parallelism
+        let encoded_password = "$argon2id$v=19$m=1,t=3,p=1$QUFBQUFBQUFBQUFBQUFBQQ$hmmTNyJlwbb6HAvFoHFWF+u03fdb0F2qA+39oPlcAqo";
+
+        assert_eq!(
+            encoder.try_matches_spring_security_hash("sometestpassword", encoded_password),
+            Err(PasswordEncoderError::InvalidParameters)
+        );
+    }
+
+    #[test]
+    fn encode_with_defaults() {
+        let encoder: Argon2PasswordEncoder = Default::default();
+
+        let unencoded_password = String::from("password");
+        let encoded_password = encoder.encode_spring_security_hash(&unencoded_password).unwrap();
+
+        assert!(encoded_password.starts_with("$argon2id$v=19$m=4096,t=3,p=1$"));
+        assert!(encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+    }
+
+    #[test]
+    fn encode_with_custom_params() {
+        let encoder = Argon2PasswordEncoder::new(20, 64, 4, 256, 4);
+
+        let unencoded_password = String::from("secret");
+        let encoded_password = encoder.encode_spring_security_hash(&unencoded_password).unwrap();
+
+        assert!(encoded_password.starts_with("$argon2id$v=19$m=256,t=4,p=4$"));
+
+        // different params must still verify with a default configured encoder
+        let default_encoder: Argon2PasswordEncoder = Default::default();
+        assert!(default_encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+    }
+
+    #[test]
+    fn encode_uses_random_salt() {
+        let encoder: Argon2PasswordEncoder = Default::default();
+
+        let unencoded_password = String::from("password");
+
+        assert_ne!(
+            encoder.encode_spring_security_hash(&unencoded_password).unwrap(),
+            encoder.encode_spring_security_hash(&unencoded_password).unwrap()
+        );
+    }
+
+    #[test]
+    fn encode_fails_on_invalid_params() {
+        // argon2 needs at least 8 KiB of memory per lane
+        let encoder = Argon2PasswordEncoder::new(16, 32, 4, 8, 3);
+
+        assert!(encoder.encode_spring_security_hash(&String::from("password")).is_none());
+    }
+
+    #[test]
+    fn upgrade_encoding_for_less_memory_or_iterations() {
+        let encoder: Argon2PasswordEncoder = Default::default();
+
+        assert!(!encoder.upgrade_encoding("$argon2id$v=19$m=4096,t=3,p=1$c29tZXNhbHQ$cGFzc3dvcmQ"));
+        assert!(encoder.upgrade_encoding("$argon2id$v=19$m=65536,t=2,p=4$c29tZXNhbHQ$cGFzc3dvcmQ"));
+        assert!(encoder.upgrade_encoding("$argon2id$v=19$m=1024,t=4,p=1$c29tZXNhbHQ$cGFzc3dvcmQ"));
+        // parallelism is not taken into account
+        assert!(!encoder.upgrade_encoding("$argon2id$v=19$m=4096,t=3,p=0$c29tZXNhbHQ$cGFzc3dvcmQ"));
+    }
+
+    #[test]
+    fn upgrade_encoding_for_empty_or_malformed_hash() {
+        let encoder: Argon2PasswordEncoder = Default::default();
+
+        assert_eq!(encoder.try_upgrade_encoding(""), Ok(false));
+        assert_eq!(
+            encoder.try_upgrade_encoding("$argon2x$v=19$m=4096,t=3,p=1$c29tZXNhbHQ$cGFzc3dvcmQ"),
+            Err(PasswordEncoderError::MalformedHash)
+        );
+    }
+
+    #[test]
+    fn matches_spring_security_v5_8_hash() {
+        let encoder = Argon2PasswordEncoder::defaults_for_spring_security_v5_8();
+
+        let encoded_password = "$argon2id$v=19$m=16384,t=2,p=1$QUFBQUFBQUFBQUFBQUFBQQ$Pmbo2effrXPw4gygaMJwU4VdxCu7tjuARksTVxfFE9k";
+
+        assert!(encoder.matches_spring_security_hash("password", encoded_password));
+        assert!(!encoder.upgrade_encoding(encoded_password));
+        assert!(Argon2PasswordEncoder::defaults_for_spring_security_v5_2().upgrade_encoding("$argon2id$v=19$m=4096,t=2,p=1$QUFBQUFBQUFBQUFBQUFBQQ$cGFzc3dvcmQ"));
+    }
+}
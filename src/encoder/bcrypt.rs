@@ -1,36 +1,297 @@
+use crate::codec::BCRYPT_BASE64;
+use crate::error::PasswordEncoderError;
+use crate::util::constant_time_equals;
 use crate::PasswordEncoder;
-use bcrypt::{hash_with_result, verify, Version};
-
-#[derive(Default, Clone, Copy, Debug)]
-pub struct BCryptPasswordEncoder {}
-
-impl PasswordEncoder for BCryptPasswordEncoder {
-    fn matches_spring_security_hash(&self, unencoded_password: &String, encoded_password: &String) -> bool {
-        let result = verify(unencoded_password, encoded_password);
-        if result.is_err() {
-            // println!("Got error: {}", &result.unwrap_err());
-            false
-        } else {
-            result.unwrap()
+use base64::Engine;
+use bcrypt::{bcrypt, hash_with_salt, BcryptError, Version};
+use rand_core::{CryptoRng, OsRng, RngCore};
+use std::fmt;
+use std::sync::Mutex;
+use zeroize::Zeroizing;
+
+// https://github.com/spring-projects/spring-security/blob/dc85ce016603bf32f1cb474e5399bc74a1fc0b73/crypto/src/main/java/org/springframework/security/crypto/bcrypt/BCryptPasswordEncoder.java#L100
+const DEFAULT_STRENGTH: i32 = 10;
+const MIN_STRENGTH: i32 = 4;
+const MAX_STRENGTH: i32 = 31;
+const SALT_LENGTH: usize = 16;
+const ENCODED_SALT_AND_HASH_LENGTH: usize = 53;
+const ENCODED_SALT_LENGTH: usize = 22;
+// bcrypt uses at most 72 bytes of the password, the stored hash drops the last byte of its 24 bytes output
+const MAX_PASSWORD_LENGTH: usize = 72;
+const HASH_LENGTH: usize = 23;
+const LEGACY_PREFIX: &str = "$2$";
+
+// https://github.com/spring-projects/spring-security/blob/dc85ce016603bf32f1cb474e5399bc74a1fc0b73/crypto/src/main/java/org/springframework/security/crypto/bcrypt/BCryptPasswordEncoder.java#L212
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BCryptVersion {
+    TwoA,
+    TwoY,
+    TwoB,
+}
+
+impl BCryptVersion {
+    fn version(&self) -> Version {
+        match self {
+            BCryptVersion::TwoA => Version::TwoA,
+            BCryptVersion::TwoY => Version::TwoY,
+            BCryptVersion::TwoB => Version::TwoB,
         }
     }
+}
 
-    fn encode_spring_security_hash(&self, unencoded_password: &String) -> Option<String> {
-        // https://github.com/spring-projects/spring-security/blob/dc85ce016603bf32f1cb474e5399bc74a1fc0b73/crypto/src/main/java/org/springframework/security/crypto/bcrypt/BCryptPasswordEncoder.java#L100
-        let default_cost = 10;
-        // https://github.com/spring-projects/spring-security/blob/dc85ce016603bf32f1cb474e5399bc74a1fc0b73/crypto/src/main/java/org/springframework/security/crypto/bcrypt/BCryptPasswordEncoder.java#LL79C8-L79C26
-        let default_version = Version::TwoA;
+/// Source of the salt, drawn from with a shared reference as encoding only gets one
+///
+/// `OsRng` is used as is, other random number generators are put behind a `Mutex` by `BCryptPasswordEncoder::with_random`.
+pub trait SaltSource {
+    fn fill_salt(&self, salt: &mut [u8]) -> Result<(), PasswordEncoderError>;
+}
 
-        match hash_with_result(unencoded_password, default_cost) {
-            Ok(hash_parts) => Some(hash_parts.format_for_version(default_version)),
-            _ => None,
+impl SaltSource for OsRng {
+    fn fill_salt(&self, salt: &mut [u8]) -> Result<(), PasswordEncoderError> {
+        OsRng.try_fill_bytes(salt).map_err(|_| PasswordEncoderError::RandomSourceFailure)
+    }
+}
+
+impl<R: RngCore + CryptoRng> SaltSource for Mutex<R> {
+    fn fill_salt(&self, salt: &mut [u8]) -> Result<(), PasswordEncoderError> {
+        let mut random = self.lock().map_err(|_| PasswordEncoderError::RandomSourceFailure)?;
+        random.try_fill_bytes(salt).map_err(|_| PasswordEncoderError::RandomSourceFailure)
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct BCryptPasswordEncoder<S = OsRng> {
+    version: BCryptVersion,
+    strength: u32,
+    random: S,
+}
+
+impl Default for BCryptPasswordEncoder {
+    fn default() -> BCryptPasswordEncoder {
+        BCryptPasswordEncoder {
+            // https://github.com/spring-projects/spring-security/blob/dc85ce016603bf32f1cb474e5399bc74a1fc0b73/crypto/src/main/java/org/springframework/security/crypto/bcrypt/BCryptPasswordEncoder.java#LL79C8-L79C26
+            version: BCryptVersion::TwoA,
+            strength: DEFAULT_STRENGTH as u32,
+            random: OsRng,
         }
     }
 }
 
+// https://github.com/spring-projects/spring-security/blob/dc85ce016603bf32f1cb474e5399bc74a1fc0b73/crypto/src/main/java/org/springframework/security/crypto/bcrypt/BCryptPasswordEncoder.java#L100-L104
+fn checked_strength(strength: i32) -> Option<u32> {
+    let strength = if strength == -1 { DEFAULT_STRENGTH } else { strength };
+    if !(MIN_STRENGTH..=MAX_STRENGTH).contains(&strength) {
+        return None;
+    }
+    Some(strength as u32)
+}
+
+impl BCryptPasswordEncoder {
+    /// Same parameters as the Spring Security constructor, a strength of -1 selects the default of 10
+    /// and nothing is returned when Spring Security would throw
+    pub fn new(version: BCryptVersion, strength: i32) -> Option<BCryptPasswordEncoder> {
+        Some(BCryptPasswordEncoder {
+            version,
+            strength: checked_strength(strength)?,
+            random: OsRng,
+        })
+    }
+}
+
+impl<R: RngCore + CryptoRng> BCryptPasswordEncoder<Mutex<R>> {
+    /// Like `new`, but the salt is drawn from the given random source
+    pub fn with_random(version: BCryptVersion, strength: i32, random: R) -> Option<BCryptPasswordEncoder<Mutex<R>>> {
+        Some(BCryptPasswordEncoder {
+            version,
+            strength: checked_strength(strength)?,
+            random: Mutex::new(random),
+        })
+    }
+}
+
+impl<S> fmt::Debug for BCryptPasswordEncoder<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BCryptPasswordEncoder")
+            .field("version", &self.version)
+            .field("strength", &self.strength)
+            .finish_non_exhaustive()
+    }
+}
+
+// same as the spring security pattern \A\$2(a|y|b)?\$(\d\d)\$[./0-9A-Za-z]{53}
+// https://github.com/spring-projects/spring-security/blob/dc85ce016603bf32f1cb474e5399bc74a1fc0b73/crypto/src/main/java/org/springframework/security/crypto/bcrypt/BCryptPasswordEncoder.java#L37
+pub(crate) fn is_bcrypt_hash(encoded_password: &str) -> bool {
+    let without_version = match encoded_password.strip_prefix("$2") {
+        Some(rest) => rest.strip_prefix(['a', 'y', 'b']).unwrap_or(rest),
+        None => return false,
+    };
+    let salt_and_hash = match without_version.strip_prefix('$') {
+        Some(rest) if rest.len() > 3 && rest.as_bytes()[..2].iter().all(u8::is_ascii_digit) && rest[2..].starts_with('$') => &rest[3..],
+        _ => return false,
+    };
+
+    let bcrypt_base64 = salt_and_hash.bytes().all(|c| matches!(c, b'.' | b'/') || c.is_ascii_alphanumeric());
+    salt_and_hash.len() == ENCODED_SALT_AND_HASH_LENGTH && bcrypt_base64
+}
+
+// version and cost of a hash passing `is_bcrypt_hash`, the version is missing for the legacy "$2$" prefix
+pub(crate) fn decode_version_and_cost(encoded_password: &str) -> Option<(Option<BCryptVersion>, u32)> {
+    let mut parts = encoded_password.split('$').skip(1);
+    let version = match parts.next()? {
+        "2a" => Some(BCryptVersion::TwoA),
+        "2y" => Some(BCryptVersion::TwoY),
+        "2b" => Some(BCryptVersion::TwoB),
+        _ => None,
+    };
+    let cost = parts.next()?.parse::<u32>().ok()?;
+    Some((version, cost))
+}
+
+// cost, salt and hash of a hash passing `is_bcrypt_hash`
+fn decode_hash(encoded_password: &str) -> Result<(u32, [u8; SALT_LENGTH], Vec<u8>), PasswordEncoderError> {
+    let (_, cost) = decode_version_and_cost(encoded_password).ok_or(PasswordEncoderError::MalformedHash)?;
+    if !(MIN_STRENGTH as u32..=MAX_STRENGTH as u32).contains(&cost) {
+        return Err(PasswordEncoderError::InvalidParameters);
+    }
+    let salt_and_hash = &encoded_password[encoded_password.len() - ENCODED_SALT_AND_HASH_LENGTH..];
+    let (encoded_salt, encoded_hash) = salt_and_hash.split_at(ENCODED_SALT_LENGTH);
+    let salt = BCRYPT_BASE64.decode(encoded_salt).map_err(|_| PasswordEncoderError::MalformedHash)?;
+    let salt: [u8; SALT_LENGTH] = salt.try_into().map_err(|_| PasswordEncoderError::MalformedHash)?;
+    let hash = BCRYPT_BASE64.decode(encoded_hash).map_err(|_| PasswordEncoderError::MalformedHash)?;
+    Ok((cost, salt, hash))
+}
+
+// hashes like the bcrypt crate does, which appends a terminating zero byte to the password and truncates it to 72 bytes,
+// the legacy "$2$" prefix hashes the password without that zero byte, nothing is returned for an empty password which can not be hashed without it
+// https://github.com/spring-projects/spring-security/blob/dc85ce016603bf32f1cb474e5399bc74a1fc0b73/crypto/src/main/java/org/springframework/security/crypto/bcrypt/BCrypt.java
+fn hash_password(unencoded_password: &[u8], cost: u32, salt: [u8; SALT_LENGTH], terminated: bool) -> Option<Zeroizing<[u8; 24]>> {
+    let mut password = Zeroizing::new(Vec::with_capacity(unencoded_password.len() + 1));
+    password.extend_from_slice(unencoded_password);
+    if terminated {
+        password.push(0);
+    }
+    if password.is_empty() {
+        return None;
+    }
+    let password_length = password.len().min(MAX_PASSWORD_LENGTH);
+    Some(Zeroizing::new(bcrypt(cost, salt, &password[..password_length])))
+}
+
+fn to_password_encoder_error(error: BcryptError) -> PasswordEncoderError {
+    match error {
+        BcryptError::CostNotAllowed(_) => PasswordEncoderError::InvalidParameters,
+        _ => PasswordEncoderError::MalformedHash,
+    }
+}
+
+impl<S: SaltSource> PasswordEncoder for BCryptPasswordEncoder<S> {
+    fn matches_spring_security_hash(&self, unencoded_password: &str, encoded_password: &str) -> bool {
+        self.try_matches_spring_security_hash(unencoded_password, encoded_password).unwrap_or(false)
+    }
+
+    fn encode_spring_security_hash(&self, unencoded_password: &str) -> Option<String> {
+        self.try_encode_spring_security_hash(unencoded_password).ok()
+    }
+
+    fn try_matches_spring_security_hash_bytes(&self, unencoded_password: &[u8], encoded_password: &str) -> Result<bool, PasswordEncoderError> {
+        if !is_bcrypt_hash(encoded_password) {
+            return Err(PasswordEncoderError::MalformedHash);
+        }
+        let (cost, salt, stored_hash) = decode_hash(encoded_password)?;
+        match hash_password(unencoded_password, cost, salt, !encoded_password.starts_with(LEGACY_PREFIX)) {
+            Some(hash) => Ok(constant_time_equals(&stored_hash, &hash[..HASH_LENGTH])),
+            None => Ok(false),
+        }
+    }
+
+    fn try_encode_spring_security_hash_bytes(&self, unencoded_password: &[u8]) -> Result<String, PasswordEncoderError> {
+        let mut salt = [0u8; SALT_LENGTH];
+        self.random.fill_salt(&mut salt)?;
+
+        let hash_parts = hash_with_salt(unencoded_password, self.strength, salt).map_err(to_password_encoder_error)?;
+        Ok(hash_parts.format_for_version(self.version.version()))
+    }
+
+    /// Upgrades hashes with a lower strength than configured, the version is not taken into account
+    fn try_upgrade_encoding(&self, encoded_password: &str) -> Result<bool, PasswordEncoderError> {
+        // https://github.com/spring-projects/spring-security/blob/dc85ce016603bf32f1cb474e5399bc74a1fc0b73/crypto/src/main/java/org/springframework/security/crypto/bcrypt/BCryptPasswordEncoder.java
+        if encoded_password.is_empty() {
+            return Ok(false);
+        }
+        if !is_bcrypt_hash(encoded_password) {
+            return Err(PasswordEncoderError::MalformedHash);
+        }
+        let (_, cost) = decode_version_and_cost(encoded_password).ok_or(PasswordEncoderError::MalformedHash)?;
+        Ok(cost < self.strength)
+    }
+}
+
+#[cfg(test)]
+mod pattern_tests {
+    use super::is_bcrypt_hash;
+
+    #[test]
+    fn accepts_all_spring_versions() {
+        assert!(is_bcrypt_hash("$2a$10$dXJ3SW6G7P50lGmMkkmwe.20cQQubK3.HZWzG3YB1tlRy.fqvM/BG"));
+        assert!(is_bcrypt_hash("$2b$10$dXJ3SW6G7P50lGmMkkmwe.20cQQubK3.HZWzG3YB1tlRy.fqvM/BG"));
+        assert!(is_bcrypt_hash("$2y$10$dXJ3SW6G7P50lGmMkkmwe.20cQQubK3.HZWzG3YB1tlRy.fqvM/BG"));
+        assert!(is_bcrypt_hash("$2$10$dXJ3SW6G7P50lGmMkkmwe.20cQQubK3.HZWzG3YB1tlRy.fqvM/BG"));
+    }
+
+    #[test]
+    fn rejects_unknown_version() {
+        assert!(!is_bcrypt_hash("$2x$10$dXJ3SW6G7P50lGmMkkmwe.20cQQubK3.HZWzG3YB1tlRy.fqvM/BG"));
+        assert!(!is_bcrypt_hash("$3a$10$dXJ3SW6G7P50lGmMkkmwe.20cQQubK3.HZWzG3YB1tlRy.fqvM/BG"));
+    }
+
+    #[test]
+    fn rejects_malformed_cost() {
+        assert!(!is_bcrypt_hash("$2a$1$dXJ3SW6G7P50lGmMkkmwe.20cQQubK3.HZWzG3YB1tlRy.fqvM/BG"));
+        assert!(!is_bcrypt_hash("$2a$1a$dXJ3SW6G7P50lGmMkkmwe.20cQQubK3.HZWzG3YB1tlRy.fqvM/BG"));
+    }
+
+    #[test]
+    fn rejects_wrong_length_or_characters() {
+        assert!(!is_bcrypt_hash("$2a$10$dXJ3SW6G7P50lGmMkkmwe.20cQQubK3.HZWzG3YB1tlRy.fqvM/B"));
+        assert!(!is_bcrypt_hash("$2a$10$dXJ3SW6G7P50lGmMkkmwe.20cQQubK3.HZWzG3YB1tlRy.fqvM/BGG"));
+        assert!(!is_bcrypt_hash("$2a$10$dXJ3SW6G7P50lGmMkkmwe+20cQQubK3.HZWzG3YB1tlRy.fqvM/BG"));
+        assert!(!is_bcrypt_hash(""));
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{BCryptPasswordEncoder, PasswordEncoder};
+    use super::{BCryptPasswordEncoder, BCryptVersion, PasswordEncoder};
+    use crate::error::PasswordEncoderError;
+    use rand_core::{CryptoRng, RngCore};
+    use std::num::NonZeroU32;
+
+    // repeats the given bytes, only to get predictable salts
+    struct RepeatingRng(Vec<u8>, usize);
+
+    impl RngCore for RepeatingRng {
+        fn next_u32(&mut self) -> u32 {
+            rand_core::impls::next_u32_via_fill(self)
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            rand_core::impls::next_u64_via_fill(self)
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            for byte in dest {
+                *byte = self.0[self.1 % self.0.len()];
+                self.1 += 1;
+            }
+        }
+
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+            self.fill_bytes(dest);
+            Ok(())
+        }
+    }
+
+    impl CryptoRng for RepeatingRng {}
 
     #[test]
     fn check_when_no_rounds_then_true() {
@@ -75,4 +336,178 @@ mod tests {
 
         assert!(!encoder.matches_spring_security_hash(&unencoded_password, &stored_encoded_password));
     }
+
+    #[test]
+    fn no_match_for_malformed_hash() {
+        let encoder: BCryptPasswordEncoder = Default::default();
+
+        let unencoded_password = String::from("password");
+
+        assert!(!encoder.matches_spring_security_hash(&unencoded_password, ""));
+        assert!(!encoder.matches_spring_security_hash(&unencoded_password, &String::from("password")));
+        assert!(!encoder.matches_spring_security_hash(&unencoded_password, &String::from("$2x$10$dXJ3SW6G7P50lGmMkkmwe.20cQQubK3.HZWzG3YB1tlRy.fqvM/BG")));
+    }
+
+    #[test]
+    fn matches_other_versions() {
+        let encoder: BCryptPasswordEncoder = Default::default();
+
+        let unencoded_password = String::from("password");
+
+        assert!(encoder.matches_spring_security_hash(&unencoded_password, &String::from("$2b$10$dXJ3SW6G7P50lGmMkkmwe.20cQQubK3.HZWzG3YB1tlRy.fqvM/BG")));
+        assert!(encoder.matches_spring_security_hash(&unencoded_password, &String::from("$2y$10$dXJ3SW6G7P50lGmMkkmwe.20cQQubK3.HZWzG3YB1tlRy.fqvM/BG")));
+    }
+
+    #[test]
+    fn matches_legacy_hash_without_terminator() {
+        let encoder: BCryptPasswordEncoder = Default::default();
+
+        // the $2a$ hash of "password" is the $2$ hash of the password with its terminating zero byte
+        let encoded_password = "$2$10$dXJ3SW6G7P50lGmMkkmwe.20cQQubK3.HZWzG3YB1tlRy.fqvM/BG";
+
+        assert_eq!(encoder.try_matches_spring_security_hash_bytes(b"password\0", encoded_password), Ok(true));
+        assert_eq!(encoder.try_matches_spring_security_hash("password", encoded_password), Ok(false));
+        assert_eq!(encoder.try_matches_spring_security_hash("", encoded_password), Ok(false));
+        assert_eq!(
+            encoder.try_matches_spring_security_hash("password", "$2$03$dXJ3SW6G7P50lGmMkkmwe.20cQQubK3.HZWzG3YB1tlRy.fqvM/BG"),
+            Err(PasswordEncoderError::InvalidParameters)
+        );
+    }
+
+    #[test]
+    fn matches_own_legacy_hash() {
+        let encoder = BCryptPasswordEncoder::new(BCryptVersion::TwoA, 4).unwrap();
+
+        let encoded_password = encoder.encode_spring_security_hash("password").unwrap().replacen("$2a$", "$2$", 1);
+
+        assert_eq!(encoder.try_matches_spring_security_hash_bytes(b"password\0", &encoded_password), Ok(true));
+    }
+
+    #[test]
+    fn error_for_malformed_hash() {
+        let encoder: BCryptPasswordEncoder = Default::default();
+
+        assert_eq!(encoder.try_matches_spring_security_hash("password", "password"), Err(PasswordEncoderError::MalformedHash));
+        assert_eq!(
+            encoder.try_matches_spring_security_hash("password", "$2a$00$9N8N35BVs5TLqGL3pspAte5OWWA2a2aZIs.EGp7At7txYakFERMue"),
+            Err(PasswordEncoderError::InvalidParameters)
+        );
+        assert_eq!(
+            encoder.try_matches_spring_security_hash("wrongpassword", "$2a$10$dXJ3SW6G7P50lGmMkkmwe.20cQQubK3.HZWzG3YB1tlRy.fqvM/BG"),
+            Ok(false)
+        );
+    }
+
+    #[test]
+    fn error_for_failing_random() {
+        struct FailingRng;
+
+        impl RngCore for FailingRng {
+            fn next_u32(&mut self) -> u32 {
+                0
+            }
+
+            fn next_u64(&mut self) -> u64 {
+                0
+            }
+
+            fn fill_bytes(&mut self, _dest: &mut [u8]) {}
+
+            fn try_fill_bytes(&mut self, _dest: &mut [u8]) -> Result<(), rand_core::Error> {
+                Err(rand_core::Error::from(NonZeroU32::new(rand_core::Error::CUSTOM_START).unwrap()))
+            }
+        }
+
+        impl CryptoRng for FailingRng {}
+
+        let encoder = BCryptPasswordEncoder::with_random(BCryptVersion::TwoA, 4, FailingRng).unwrap();
+
+        assert_eq!(encoder.try_encode_spring_security_hash("password"), Err(PasswordEncoderError::RandomSourceFailure));
+    }
+
+    #[test]
+    fn default_encoder_is_clone_and_copy() {
+        fn cloned<T: Clone>(encoder: &T) -> T {
+            encoder.clone()
+        }
+
+        fn copied<T: Copy>(encoder: &T) -> T {
+            *encoder
+        }
+
+        let encoder = BCryptPasswordEncoder::default();
+        let cloned = cloned(&encoder);
+        let copied = copied(&encoder);
+
+        let encoded_password = cloned.encode_spring_security_hash("password").unwrap();
+        assert!(copied.matches_spring_security_hash("password", &encoded_password));
+    }
+
+    #[test]
+    fn new_with_default_strength() {
+        let encoder = BCryptPasswordEncoder::new(BCryptVersion::TwoY, -1).unwrap();
+
+        let unencoded_password = String::from("password");
+        let encoded_password = encoder.encode_spring_security_hash(&unencoded_password).unwrap();
+
+        assert!(encoded_password.starts_with("$2y$10$"));
+        assert!(encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+    }
+
+    #[test]
+    fn new_with_version_and_strength() {
+        let encoder = BCryptPasswordEncoder::new(BCryptVersion::TwoB, 12).unwrap();
+
+        let unencoded_password = String::from("password");
+        let encoded_password = encoder.encode_spring_security_hash(&unencoded_password).unwrap();
+
+        assert!(encoded_password.starts_with("$2b$12$"));
+        assert_eq!(encoded_password.len(), 60);
+        assert!(encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+    }
+
+    #[test]
+    fn new_rejects_strength_out_of_range() {
+        assert!(BCryptPasswordEncoder::new(BCryptVersion::TwoA, 3).is_none());
+        assert!(BCryptPasswordEncoder::new(BCryptVersion::TwoA, 32).is_none());
+        assert!(BCryptPasswordEncoder::new(BCryptVersion::TwoA, -2).is_none());
+        assert!(BCryptPasswordEncoder::new(BCryptVersion::TwoA, 4).is_some());
+        assert!(BCryptPasswordEncoder::new(BCryptVersion::TwoA, 31).is_some());
+    }
+
+    #[test]
+    fn encode_with_given_random() {
+        // the bytes behind the salt "CCCCCCCCCCCCCCCCCCCCC." of the OpenBSD test vectors
+        let random = RepeatingRng(vec![0x10, 0x41, 0x04], 0);
+        let encoder = BCryptPasswordEncoder::with_random(BCryptVersion::TwoA, 5, random).unwrap();
+
+        let unencoded_password = String::from("U*U");
+        let encoded_password = encoder.encode_spring_security_hash(&unencoded_password).unwrap();
+
+        assert_eq!(encoded_password, "$2a$05$CCCCCCCCCCCCCCCCCCCCC.E5YPO9kmyuRGyh0XouQYb4YMJKvyOeW");
+    }
+
+    #[test]
+    fn upgrade_encoding_for_lower_strength() {
+        let encoder: BCryptPasswordEncoder = Default::default();
+
+        assert!(encoder.upgrade_encoding("$2a$05$CCCCCCCCCCCCCCCCCCCCC.E5YPO9kmyuRGyh0XouQYb4YMJKvyOeW"));
+        assert!(!encoder.upgrade_encoding("$2a$10$dXJ3SW6G7P50lGmMkkmwe.20cQQubK3.HZWzG3YB1tlRy.fqvM/BG"));
+        assert!(!encoder.upgrade_encoding("$2b$12$dXJ3SW6G7P50lGmMkkmwe.20cQQubK3.HZWzG3YB1tlRy.fqvM/BG"));
+    }
+
+    #[test]
+    fn upgrade_encoding_ignores_version() {
+        let encoder = BCryptPasswordEncoder::new(BCryptVersion::TwoB, 5).unwrap();
+
+        assert!(!encoder.upgrade_encoding("$2a$05$CCCCCCCCCCCCCCCCCCCCC.E5YPO9kmyuRGyh0XouQYb4YMJKvyOeW"));
+    }
+
+    #[test]
+    fn upgrade_encoding_for_empty_or_malformed_hash() {
+        let encoder: BCryptPasswordEncoder = Default::default();
+
+        assert_eq!(encoder.try_upgrade_encoding(""), Ok(false));
+        assert_eq!(encoder.try_upgrade_encoding("$2a$05$short"), Err(PasswordEncoderError::MalformedHash));
+    }
 }
@@ -1,135 +1,236 @@
-use crate::encoder::bcrypt::BCryptPasswordEncoder;
-use crate::encoder::md4::Md4PasswordEncoder;
-use crate::encoder::md5::Md5PasswordEncoder;
-use crate::encoder::noop::NoOpPasswordEncoder;
-use crate::PasswordEncoder;
-// use std::collections::HashMap;
-
-#[derive(Debug)]
+use crate::error::PasswordEncoderError;
+use crate::{Encoder, PasswordEncoder};
+use std::collections::HashMap;
+use std::fmt;
+
 pub struct DelegatingPasswordEncoder {
     id_prefix: String,
     id_suffix: String,
-    default_encoder: String,
-    // encoders: HashMap<String, Box<dyn PasswordEncoder>>,
+    id_for_encode: String,
+    encoders: HashMap<String, Box<dyn PasswordEncoder + Send + Sync>>,
+    // without any, matching fails like the UnmappedIdPasswordEncoder of spring security
+    // https://github.com/spring-projects/spring-security/blob/0e3ee8990299436223c3037afb096f454f17ef5e/crypto/src/main/java/org/springframework/security/crypto/password/DelegatingPasswordEncoder.java#L300
+    default_password_encoder_for_matches: Option<Box<dyn PasswordEncoder + Send + Sync>>,
 }
 
 impl Default for DelegatingPasswordEncoder {
     fn default() -> DelegatingPasswordEncoder {
-        /* let mut config = */
+        // https://github.com/spring-projects/spring-security/blob/0e3ee8990299436223c3037afb096f454f17ef5e/crypto/src/main/java/org/springframework/security/crypto/factory/PasswordEncoderFactories.java#L72
+        let encoders = Encoder::ALL
+            .iter()
+            .filter(|encoder| **encoder != Encoder::DELEGATING)
+            .map(|encoder| (encoder.to_string(), encoder.create_password_encoder()))
+            .collect();
+
         DelegatingPasswordEncoder {
             id_prefix: String::from("{"),
             id_suffix: String::from("}"),
-            default_encoder: String::from("bcrypt"),
-            // encoders: HashMap::new(),
+            id_for_encode: String::from("bcrypt"),
+            encoders,
+            default_password_encoder_for_matches: None,
         }
-        /*
-        config.encoders.insert(
-            String::from("noop"),
-            Box::new(crate::encoder::noop::NoOpPasswordEncoder {}),
-        );
-        config
-         */
     }
 }
 
-// somehow I have so much trouble to have this using dyn trait mechanics ... still learning Rust :D
-/*
-fn get_encoder_for_id<'a>(
-    encoder_id: &'a String,
-    encoders: &'a HashMap<String, Box<dyn PasswordEncoder>>,
-) -> Option<&'a Box<dyn PasswordEncoder>> {
-    if !encoders.contains_key(encoder_id) {
-        return None;
+impl DelegatingPasswordEncoder {
+    /// Same parameters as the Spring Security constructor, returns nothing when no encoder is mapped for the id used to encode
+    pub fn new(id_for_encode: &str, encoders: HashMap<String, Box<dyn PasswordEncoder + Send + Sync>>) -> Option<DelegatingPasswordEncoder> {
+        if !encoders.contains_key(id_for_encode) {
+            return None;
+        }
+
+        Some(DelegatingPasswordEncoder {
+            id_prefix: String::from("{"),
+            id_suffix: String::from("}"),
+            id_for_encode: id_for_encode.to_string(),
+            encoders,
+            default_password_encoder_for_matches: None,
+        })
     }
-    encoders.get(encoder_id)
+
+    /// Maps the encoder to the id, replacing any encoder mapped before
+    pub fn register_encoder<E: PasswordEncoder + Send + Sync + 'static>(&mut self, id: &str, encoder: E) {
+        self.encoders.insert(id.to_string(), Box::new(encoder));
+    }
+
+    /// Selects the id used to encode, returns false and keeps the current one when no encoder is mapped for the id
+    pub fn set_id_for_encode(&mut self, id_for_encode: &str) -> bool {
+        if !self.encoders.contains_key(id_for_encode) {
+            return false;
+        }
+        self.id_for_encode = id_for_encode.to_string();
+        true
+    }
+
+    /// Used to match encoded passwords without an id or with an id no encoder is mapped for,
+    /// the whole encoded password including any unmapped id is passed on
+    pub fn set_default_password_encoder_for_matches<E: PasswordEncoder + Send + Sync + 'static>(&mut self, encoder: E) {
+        self.default_password_encoder_for_matches = Some(Box::new(encoder));
+    }
+
+    /// Starts with the same markers, id used to encode and encoders as `Default`
+    pub fn builder() -> DelegatingPasswordEncoderBuilder {
+        let encoder: DelegatingPasswordEncoder = Default::default();
+        DelegatingPasswordEncoderBuilder {
+            id_prefix: encoder.id_prefix,
+            id_suffix: encoder.id_suffix,
+            id_for_encode: encoder.id_for_encode,
+            encoders: encoder.encoders,
+            default_password_encoder_for_matches: encoder.default_password_encoder_for_matches,
+        }
+    }
+}
+
+/// Builds a delegating password encoder with custom id markers, starting with the same encoders as `Default`
+pub struct DelegatingPasswordEncoderBuilder {
+    id_prefix: String,
+    id_suffix: String,
+    id_for_encode: String,
+    encoders: HashMap<String, Box<dyn PasswordEncoder + Send + Sync>>,
+    default_password_encoder_for_matches: Option<Box<dyn PasswordEncoder + Send + Sync>>,
+}
+
+impl DelegatingPasswordEncoderBuilder {
+    /// Marker in front of the id, may be empty
+    pub fn with_id_prefix(mut self, id_prefix: &str) -> Self {
+        self.id_prefix = id_prefix.to_string();
+        self
+    }
+
+    /// Marker behind the id, must not be empty
+    pub fn with_id_suffix(mut self, id_suffix: &str) -> Self {
+        self.id_suffix = id_suffix.to_string();
+        self
+    }
+
+    /// Id used to encode, `build` fails when no encoder is mapped for it
+    pub fn with_id_for_encode(mut self, id_for_encode: &str) -> Self {
+        self.id_for_encode = id_for_encode.to_string();
+        self
+    }
+
+    /// Replaces all encoders, including the default ones
+    pub fn with_encoders(mut self, encoders: HashMap<String, Box<dyn PasswordEncoder + Send + Sync>>) -> Self {
+        self.encoders = encoders;
+        self
+    }
+
+    /// Maps the encoder to the id, replacing any encoder mapped before
+    pub fn with_encoder<E: PasswordEncoder + Send + Sync + 'static>(mut self, id: &str, encoder: E) -> Self {
+        self.encoders.insert(id.to_string(), Box::new(encoder));
+        self
+    }
+
+    /// Like `DelegatingPasswordEncoder::set_default_password_encoder_for_matches`
+    pub fn with_default_password_encoder_for_matches<E: PasswordEncoder + Send + Sync + 'static>(mut self, encoder: E) -> Self {
+        self.default_password_encoder_for_matches = Some(Box::new(encoder));
+        self
+    }
+
+    /// Fails for an empty suffix or ids containing a marker like Spring Security does,
+    /// unlike Spring Security the prefix may contain the suffix
+    pub fn build(self) -> Result<DelegatingPasswordEncoder, PasswordEncoderError> {
+        // https://github.com/spring-projects/spring-security/blob/0e3ee8990299436223c3037afb096f454f17ef5e/crypto/src/main/java/org/springframework/security/crypto/password/DelegatingPasswordEncoder.java
+        if self.id_suffix.is_empty() {
+            return Err(PasswordEncoderError::InvalidParameters);
+        }
+        let contains_marker = |id: &String| (!self.id_prefix.is_empty() && id.contains(&self.id_prefix)) || id.contains(&self.id_suffix);
+        if self.encoders.keys().any(contains_marker) {
+            return Err(PasswordEncoderError::InvalidParameters);
+        }
+        if !self.encoders.contains_key(&self.id_for_encode) {
+            return Err(PasswordEncoderError::UnmappedEncoderId(self.id_for_encode));
+        }
+
+        Ok(DelegatingPasswordEncoder {
+            id_prefix: self.id_prefix,
+            id_suffix: self.id_suffix,
+            id_for_encode: self.id_for_encode,
+            encoders: self.encoders,
+            default_password_encoder_for_matches: self.default_password_encoder_for_matches,
+        })
+    }
+}
+
+impl fmt::Debug for DelegatingPasswordEncoderBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut ids: Vec<&String> = self.encoders.keys().collect();
+        ids.sort();
+
+        f.debug_struct("DelegatingPasswordEncoderBuilder")
+            .field("id_prefix", &self.id_prefix)
+            .field("id_suffix", &self.id_suffix)
+            .field("id_for_encode", &self.id_for_encode)
+            .field("encoders", &ids)
+            .finish_non_exhaustive()
+    }
+}
+
+impl fmt::Debug for DelegatingPasswordEncoder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut ids: Vec<&String> = self.encoders.keys().collect();
+        ids.sort();
+
+        f.debug_struct("DelegatingPasswordEncoder")
+            .field("id_prefix", &self.id_prefix)
+            .field("id_suffix", &self.id_suffix)
+            .field("id_for_encode", &self.id_for_encode)
+            .field("encoders", &ids)
+            .finish_non_exhaustive()
+    }
+}
+
+fn get_encoder_for_id<'a>(encoder_id: &str, encoders: &'a HashMap<String, Box<dyn PasswordEncoder + Send + Sync>>) -> Option<&'a (dyn PasswordEncoder + Send + Sync)> {
+    encoders.get(encoder_id).map(|encoder| encoder.as_ref())
 }
 
 #[cfg(test)]
 mod test_get_encoder_for_id {
     use super::get_encoder_for_id;
+    use crate::encoder::bcrypt::BCryptPasswordEncoder;
+    use crate::encoder::noop::NoOpPasswordEncoder;
     use crate::PasswordEncoder;
     use std::collections::HashMap;
-    use std::ops::Deref;
 
     #[test]
     fn no_encoders_in_map() {
-        let encoder_id = String::from("noop");
-        let encoders: HashMap<String, Box<dyn PasswordEncoder>> = HashMap::new();
-        let result = get_encoder_for_id(&encoder_id, &encoders);
+        let encoders: HashMap<String, Box<dyn PasswordEncoder + Send + Sync>> = HashMap::new();
+        let result = get_encoder_for_id("noop", &encoders);
         assert!(result.is_none(), "should not find any encoder in empty list");
     }
 
     #[test]
-    fn no_matching_encoders_in_map(){
-        let encoder_id = String::from("noop");
-        let different_encoder_id = String::from("different");
-        let mut encoders: HashMap<String, Box<dyn PasswordEncoder>> = HashMap::new();
-        encoders.insert(
-            different_encoder_id,
-            Box::new(crate::encoder::noop::NoOpPasswordEncoder {}),
-        );
+    fn no_matching_encoders_in_map() {
+        let mut encoders: HashMap<String, Box<dyn PasswordEncoder + Send + Sync>> = HashMap::new();
+        encoders.insert(String::from("different"), Box::new(NoOpPasswordEncoder {}));
 
-        let result = get_encoder_for_id(&encoder_id, &encoders);
-        // assert_eq! does not work, so use matches! inside
-        // this was a GIANT rabbit whole to follow the Box<dyn Trait> bunny
-        // https://users.rust-lang.org/t/issues-in-asserting-result/61198/2
+        let result = get_encoder_for_id("noop", &encoders);
         assert!(result.is_none(), "should not find encoder as it is not in the list");
     }
 
     #[test]
-    fn finds_encoders_in_map(){
-        let encoder_id = String::from("noop");
-        let noop_encoder_box = Box::new(crate::encoder::noop::NoOpPasswordEncoder {});
-        let mut encoders: HashMap<String, Box<dyn PasswordEncoder>> = HashMap::new();
-        encoders.insert(
-            encoder_id.clone(),
-            noop_encoder_box,
-        );
-
-        let result = get_encoder_for_id(&encoder_id, &encoders);
-        assert_eq!(result.unwrap().deref(), noop_encoder_box.deref(), "should find the encoder");
-    }
-
-    #[test]
-    fn finds_the_wanted_encoders_in_map(){
-        let encoder_id = String::from("noop");
-        let noop_encoder = crate::encoder::noop::NoOpPasswordEncoder {};
-        let noop_encoder_box = Box::new(noop_encoder);
-
-        let different_encoder_id = String::from("bcrypt");
-        let different_encoder = crate::encoder::bcrypt::BCryptPasswordEncoder {};
-        let different_encoder_box = Box::new(different_encoder);
+    fn finds_the_wanted_encoders_in_map() {
+        let mut encoders: HashMap<String, Box<dyn PasswordEncoder + Send + Sync>> = HashMap::new();
+        encoders.insert(String::from("noop"), Box::new(NoOpPasswordEncoder {}));
+        encoders.insert(String::from("bcrypt"), Box::<BCryptPasswordEncoder>::default());
 
-        let mut encoders: HashMap<String, Box<dyn PasswordEncoder>> = HashMap::new();
-        encoders.insert(
-            encoder_id.clone(),
-            noop_encoder_box,
-        );
-        encoders.insert(
-            different_encoder_id.clone(),
-            different_encoder_box,
-        );
-
-        let result = get_encoder_for_id(&different_encoder_id, &encoders);
-
-        // assert_eq! does not work, so use matches! inside
-        // this was a GIANT rabbit whole to follow the Box<dyn Trait> bunny
+        // comparing the data pointers, as trait objects can not be compared with assert_eq!
         // https://users.rust-lang.org/t/issues-in-asserting-result/61198/2
-        assert_ne!(result.unwrap() as *const _, &noop_encoder as *const _, "should not find an unwanted encoder");
-        assert_eq!(result.unwrap() as *const _, &different_encoder as *const _, "should find the wanted encoder, not just any");
-    }
-}
- */
+        let wanted = encoders.get("bcrypt").unwrap().as_ref() as *const _ as *const u8;
+        let unwanted = encoders.get("noop").unwrap().as_ref() as *const _ as *const u8;
 
-fn get_encoder_id_from_encoded_password(encoded_password: &String, id_prefix: &String, id_suffix: &String) -> Option<String> {
-    if !encoded_password.starts_with(id_prefix) || !encoded_password.contains(id_suffix) {
-        return None;
+        let result = get_encoder_for_id("bcrypt", &encoders).unwrap() as *const _ as *const u8;
+        assert_ne!(result, unwanted, "should not find an unwanted encoder");
+        assert_eq!(result, wanted, "should find the wanted encoder, not just any");
     }
+}
 
-    encoded_password
+pub(crate) fn get_encoder_id_from_encoded_password(encoded_password: &str, id_prefix: &str, id_suffix: &str) -> Option<String> {
+    // the suffix is searched behind the prefix only, so both can be the same marker like "$$"
+    let encoded_password_without_prefix = encoded_password.strip_prefix(id_prefix)?;
+    encoded_password_without_prefix
         .find(id_suffix)
-        .and_then(|suffix_position| encoded_password.get(id_prefix.len()..suffix_position))
-        .map(|found_id| found_id.to_string())
+        .map(|suffix_position| encoded_password_without_prefix[..suffix_position].to_string())
 }
 
 #[cfg(test)]
@@ -332,102 +433,351 @@ mod test_get_encoder_id_from_encoded_password_multiple_chars_marker {
             "should find encoder id without having any '}}' at the ending, because the first suffix wins"
         );
     }
-}
 
-fn with_delegation_marker(resulting_password_hash: Option<String>, encoder_id: String, id_prefix: &String, id_suffix: &String) -> Option<String> {
-    if resulting_password_hash.is_none() {
-        return None;
+    #[test]
+    fn finds_encoder_id_with_same_prefix_and_suffix() {
+        let encoded_password = String::from("$$bcrypt$$$2a$10$dXJ3SW6G7P50lGmMkkmwe.20cQQubK3.HZWzG3YB1tlRy.fqvM/BG");
+        let prefix = String::from("$$");
+        let suffix = String::from("$$");
+        assert_eq!(get_encoder_id_from_encoded_password(&encoded_password, &prefix, &suffix), Some("bcrypt".to_string()));
     }
+}
 
-    Some(id_prefix.to_owned() + &encoder_id + id_suffix + &*resulting_password_hash.unwrap())
+fn with_delegation_marker(resulting_password_hash: &str, encoder_id: &str, id_prefix: &str, id_suffix: &str) -> String {
+    id_prefix.to_owned() + encoder_id + id_suffix + resulting_password_hash
 }
 
-fn without_delegation_marker(encoded_password_hash: &String, encoder_id: &String, id_prefix: &String, id_suffix: &String) -> String {
+pub(crate) fn without_delegation_marker(encoded_password_hash: &str, encoder_id: &str, id_prefix: &str, id_suffix: &str) -> String {
     encoded_password_hash[(id_prefix.len() + encoder_id.len() + id_suffix.len())..].to_string()
 }
 
 impl PasswordEncoder for DelegatingPasswordEncoder {
-    fn matches_spring_security_hash(&self, unencoded_password: &String, encoded_password: &String) -> bool {
+    fn matches_spring_security_hash(&self, unencoded_password: &str, encoded_password: &str) -> bool {
+        self.try_matches_spring_security_hash(unencoded_password, encoded_password).unwrap_or(false)
+    }
+
+    fn encode_spring_security_hash(&self, unencoded_password: &str) -> Option<String> {
+        self.try_encode_spring_security_hash(unencoded_password).ok()
+    }
+
+    /// Only results in an unmapped or missing id error as long as no default password encoder for matches is set
+    fn try_matches_spring_security_hash_bytes(&self, unencoded_password: &[u8], encoded_password: &str) -> Result<bool, PasswordEncoderError> {
         // find encoder id
-        let encoder_id = get_encoder_id_from_encoded_password(&encoded_password, &self.id_prefix, &self.id_suffix);
-        match encoder_id {
-            Some(encoder_id) => match encoder_id.as_str() {
-                "noop" => {
-                    let encoder: NoOpPasswordEncoder = Default::default();
-                    encoder.matches_spring_security_hash(
-                        &unencoded_password,
-                        &without_delegation_marker(&encoded_password, &encoder_id, &self.id_prefix, &self.id_suffix),
-                    )
-                }
-                "bcrypt" => {
-                    let encoder: BCryptPasswordEncoder = Default::default();
-                    encoder.matches_spring_security_hash(
-                        &unencoded_password,
-                        &without_delegation_marker(&encoded_password, &encoder_id, &self.id_prefix, &self.id_suffix),
-                    )
-                }
-                "MD4" => {
-                    let encoder: Md4PasswordEncoder = Default::default();
-                    encoder.matches_spring_security_hash(
-                        &unencoded_password,
-                        &without_delegation_marker(&encoded_password, &encoder_id, &self.id_prefix, &self.id_suffix),
-                    )
-                }
-                "MD5" => {
-                    let encoder: Md5PasswordEncoder = Default::default();
-                    encoder.matches_spring_security_hash(
-                        &unencoded_password,
-                        &without_delegation_marker(&encoded_password, &encoder_id, &self.id_prefix, &self.id_suffix),
-                    )
-                }
-                _ => todo!(),
-            },
-            None => false,
+        let encoder_id = get_encoder_id_from_encoded_password(encoded_password, &self.id_prefix, &self.id_suffix);
+        let encoder = encoder_id.as_deref().and_then(|encoder_id| get_encoder_for_id(encoder_id, &self.encoders));
+        if let (Some(encoder_id), Some(encoder)) = (&encoder_id, encoder) {
+            return encoder.try_matches_spring_security_hash_bytes(
+                unencoded_password,
+                &without_delegation_marker(encoded_password, encoder_id, &self.id_prefix, &self.id_suffix),
+            );
+        }
+
+        // no id or no encoder mapped for the id
+        match (encoder_id, &self.default_password_encoder_for_matches) {
+            (_, Some(default_encoder)) => default_encoder.try_matches_spring_security_hash_bytes(unencoded_password, encoded_password),
+            (Some(encoder_id), None) => Err(PasswordEncoderError::UnmappedEncoderId(encoder_id)),
+            (None, None) => Err(PasswordEncoderError::MissingEncoderId),
         }
     }
 
-    fn encode_spring_security_hash(&self, unencoded_password: &String) -> Option<String> {
-        return match self.default_encoder.as_str() {
-            "noop" => {
-                let encoder: NoOpPasswordEncoder = Default::default();
-                with_delegation_marker(
-                    encoder.encode_spring_security_hash(&unencoded_password),
-                    "noop".to_string(),
-                    &self.id_prefix,
-                    &self.id_suffix,
-                )
-            }
-            "bcrypt" => {
-                let encoder: BCryptPasswordEncoder = Default::default();
-                with_delegation_marker(
-                    encoder.encode_spring_security_hash(&unencoded_password),
-                    "bcrypt".to_string(),
-                    &self.id_prefix,
-                    &self.id_suffix,
-                )
-            }
-            "MD4" => {
-                let encoder: Md4PasswordEncoder = Default::default();
-                with_delegation_marker(
-                    encoder.encode_spring_security_hash(&unencoded_password),
-                    "MD4".to_string(),
-                    &self.id_prefix,
-                    &self.id_suffix,
-                )
-            }
-            "MD5" => {
-                let encoder: Md5PasswordEncoder = Default::default();
-                with_delegation_marker(
-                    encoder.encode_spring_security_hash(&unencoded_password),
-                    "MD%".to_string(),
-                    &self.id_prefix,
-                    &self.id_suffix,
-                )
-            }
-            _ => {
-                // TODO
-                None
-            }
+    fn try_encode_spring_security_hash_bytes(&self, unencoded_password: &[u8]) -> Result<String, PasswordEncoderError> {
+        let encoder = get_encoder_for_id(&self.id_for_encode, &self.encoders).ok_or_else(|| PasswordEncoderError::UnmappedEncoderId(self.id_for_encode.clone()))?;
+        let encoded_password = encoder.try_encode_spring_security_hash_bytes(unencoded_password)?;
+        Ok(with_delegation_marker(&encoded_password, &self.id_for_encode, &self.id_prefix, &self.id_suffix))
+    }
+
+    /// Upgrades encoded passwords without the id used to encode, otherwise asks the encoder mapped for the id
+    fn try_upgrade_encoding(&self, encoded_password: &str) -> Result<bool, PasswordEncoderError> {
+        // https://github.com/spring-projects/spring-security/blob/0e3ee8990299436223c3037afb096f454f17ef5e/crypto/src/main/java/org/springframework/security/crypto/password/DelegatingPasswordEncoder.java
+        let encoder_id = match get_encoder_id_from_encoded_password(encoded_password, &self.id_prefix, &self.id_suffix) {
+            Some(encoder_id) if encoder_id.eq_ignore_ascii_case(&self.id_for_encode) => encoder_id,
+            _ => return Ok(true),
         };
+        // spring security fails for an id differing in case only, encoding again is the better fix
+        match get_encoder_for_id(&encoder_id, &self.encoders) {
+            Some(encoder) => encoder.try_upgrade_encoding(&without_delegation_marker(encoded_password, &encoder_id, &self.id_prefix, &self.id_suffix)),
+            None => Ok(true),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DelegatingPasswordEncoder;
+    use crate::encoder::bcrypt::BCryptPasswordEncoder;
+    use crate::encoder::md5::Md5PasswordEncoder;
+    use crate::encoder::noop::NoOpPasswordEncoder;
+    use crate::error::PasswordEncoderError;
+    use crate::verification::Verification;
+    use crate::PasswordEncoder;
+    use std::collections::HashMap;
+
+    #[test]
+    fn is_send_and_sync() {
+        fn assert_send_and_sync<T: Send + Sync>() {}
+        assert_send_and_sync::<DelegatingPasswordEncoder>();
+    }
+
+    #[test]
+    fn new_requires_encoder_for_encode_id() {
+        let mut encoders: HashMap<String, Box<dyn PasswordEncoder + Send + Sync>> = HashMap::new();
+        encoders.insert(String::from("noop"), Box::new(NoOpPasswordEncoder {}));
+
+        assert!(DelegatingPasswordEncoder::new("bcrypt", encoders).is_none());
+    }
+
+    #[test]
+    fn encode_with_given_encoders() {
+        let mut encoders: HashMap<String, Box<dyn PasswordEncoder + Send + Sync>> = HashMap::new();
+        encoders.insert(String::from("noop"), Box::new(NoOpPasswordEncoder {}));
+        let encoder = DelegatingPasswordEncoder::new("noop", encoders).unwrap();
+
+        let unencoded_password = String::from("password");
+        let encoded_password = encoder.encode_spring_security_hash(&unencoded_password).unwrap();
+
+        assert_eq!(encoded_password, "{noop}password");
+        assert!(encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+    }
+
+    #[test]
+    fn matches_with_registered_encoder_under_custom_id() {
+        let mut encoder: DelegatingPasswordEncoder = Default::default();
+        encoder.register_encoder("md5-1024", Md5PasswordEncoder::default().with_iterations(1024).unwrap().with_encode_hash_as_base64(true));
+
+        let unencoded_password = String::from("password");
+        let encoded_password = String::from("{md5-1024}{thisissalt}ePq69JsvL46oTcxXe1fcMQ==");
+
+        assert!(encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+    }
+
+    #[test]
+    fn registered_encoder_replaces_existing_one() {
+        let mut encoder: DelegatingPasswordEncoder = Default::default();
+        encoder.register_encoder("MD5", Md5PasswordEncoder::default().with_encode_hash_as_base64(true));
+
+        let unencoded_password = String::from("password");
+
+        assert!(encoder.matches_spring_security_hash(&unencoded_password, &String::from("{MD5}{thisissalt}Kk5xBMJ4AJj1DtWoS7IyPQ==")));
+        assert!(!encoder.matches_spring_security_hash(&unencoded_password, &String::from("{MD5}{thisissalt}2a4e7104c2780098f50ed5a84bb2323d")));
+    }
+
+    #[test]
+    fn set_id_for_encode_requires_mapped_encoder() {
+        let mut encoder: DelegatingPasswordEncoder = Default::default();
+
+        assert!(!encoder.set_id_for_encode("unknown"));
+        assert!(encoder.set_id_for_encode("noop"));
+
+        let encoded_password = encoder.encode_spring_security_hash(&String::from("password")).unwrap();
+        assert_eq!(encoded_password, "{noop}password");
+    }
+
+    #[test]
+    fn no_match_without_id_by_default() {
+        let encoder: DelegatingPasswordEncoder = Default::default();
+
+        let unencoded_password = String::from("password");
+
+        assert!(!encoder.matches_spring_security_hash(&unencoded_password, &unencoded_password));
+        assert!(!encoder.matches_spring_security_hash(&unencoded_password, &String::from("$2a$10$dXJ3SW6G7P50lGmMkkmwe.20cQQubK3.HZWzG3YB1tlRy.fqvM/BG")));
+    }
+
+    #[test]
+    fn no_match_for_unmapped_id_by_default() {
+        let encoder: DelegatingPasswordEncoder = Default::default();
+
+        let unencoded_password = String::from("password");
+        let encoded_password = String::from("{unmapped}password");
+
+        assert!(!encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+    }
+
+    #[test]
+    fn matches_without_id_with_default_encoder_for_matches() {
+        let mut encoder: DelegatingPasswordEncoder = Default::default();
+        encoder.set_default_password_encoder_for_matches(BCryptPasswordEncoder::default());
+
+        let unencoded_password = String::from("password");
+        let encoded_password = String::from("$2a$10$dXJ3SW6G7P50lGmMkkmwe.20cQQubK3.HZWzG3YB1tlRy.fqvM/BG");
+
+        assert!(encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+        assert!(!encoder.matches_spring_security_hash(&String::from("wrongpassword"), &encoded_password));
+    }
+
+    #[test]
+    fn default_encoder_for_matches_gets_unmapped_id() {
+        let mut encoder: DelegatingPasswordEncoder = Default::default();
+        encoder.set_default_password_encoder_for_matches(NoOpPasswordEncoder {});
+
+        let unencoded_password = String::from("{unmapped}password");
+
+        assert!(encoder.matches_spring_security_hash(&unencoded_password, &unencoded_password));
+    }
+
+    #[test]
+    fn default_encoder_for_matches_is_not_used_for_mapped_id() {
+        let mut encoder: DelegatingPasswordEncoder = Default::default();
+        encoder.set_default_password_encoder_for_matches(NoOpPasswordEncoder {});
+
+        let unencoded_password = String::from("{noop}password");
+
+        assert!(!encoder.matches_spring_security_hash(&unencoded_password, &unencoded_password));
+    }
+
+    #[test]
+    fn error_for_unmapped_id() {
+        let encoder: DelegatingPasswordEncoder = Default::default();
+
+        let result = encoder.try_matches_spring_security_hash("password", "{foo}password");
+
+        assert_eq!(result, Err(PasswordEncoderError::UnmappedEncoderId(String::from("foo"))));
+    }
+
+    #[test]
+    fn error_for_missing_id() {
+        let encoder: DelegatingPasswordEncoder = Default::default();
+
+        let result = encoder.try_matches_spring_security_hash("password", "password");
+
+        assert_eq!(result, Err(PasswordEncoderError::MissingEncoderId));
+    }
+
+    #[test]
+    fn no_error_for_wrong_password() {
+        let encoder: DelegatingPasswordEncoder = Default::default();
+
+        assert_eq!(encoder.try_matches_spring_security_hash("wrongpassword", "{noop}password"), Ok(false));
+        assert_eq!(encoder.try_matches_spring_security_hash("password", "{noop}password"), Ok(true));
+    }
+
+    #[test]
+    fn no_error_for_unmapped_id_with_default_encoder_for_matches() {
+        let mut encoder: DelegatingPasswordEncoder = Default::default();
+        encoder.set_default_password_encoder_for_matches(NoOpPasswordEncoder {});
+
+        assert_eq!(encoder.try_matches_spring_security_hash("password", "{foo}password"), Ok(false));
+        assert_eq!(encoder.try_matches_spring_security_hash("password", "password"), Ok(true));
+    }
+
+    #[test]
+    fn debug_lists_encoder_ids() {
+        let mut encoders: HashMap<String, Box<dyn PasswordEncoder + Send + Sync>> = HashMap::new();
+        encoders.insert(String::from("noop"), Box::new(NoOpPasswordEncoder {}));
+        let encoder = DelegatingPasswordEncoder::new("noop", encoders).unwrap();
+
+        assert_eq!(
+            format!("{:?}", encoder),
+            r#"DelegatingPasswordEncoder { id_prefix: "{", id_suffix: "}", id_for_encode: "noop", encoders: ["noop"], .. }"#
+        );
+    }
+
+    #[test]
+    fn upgrade_encoding_for_other_or_missing_id() {
+        let encoder: DelegatingPasswordEncoder = Default::default();
+
+        assert!(encoder.upgrade_encoding("{MD5}{thisissalt}2a4e7104c2780098f50ed5a84bb2323d"));
+        assert!(encoder.upgrade_encoding("{unmapped}password"));
+        assert!(encoder.upgrade_encoding("password"));
+    }
+
+    #[test]
+    fn upgrade_encoding_asks_encoder_for_id() {
+        let encoder: DelegatingPasswordEncoder = Default::default();
+
+        assert!(!encoder.upgrade_encoding("{bcrypt}$2a$10$dXJ3SW6G7P50lGmMkkmwe.20cQQubK3.HZWzG3YB1tlRy.fqvM/BG"));
+        assert!(encoder.upgrade_encoding("{bcrypt}$2a$05$CCCCCCCCCCCCCCCCCCCCC.E5YPO9kmyuRGyh0XouQYb4YMJKvyOeW"));
+        // ignores case like spring security, but nothing is mapped for the id
+        assert!(encoder.upgrade_encoding("{BCRYPT}$2a$10$dXJ3SW6G7P50lGmMkkmwe.20cQQubK3.HZWzG3YB1tlRy.fqvM/BG"));
+    }
+
+    #[test]
+    fn verify_and_upgrade_legacy_hash() {
+        let encoder: DelegatingPasswordEncoder = Default::default();
+        let encoded_password = "{MD5}{thisissalt}2a4e7104c2780098f50ed5a84bb2323d";
+
+        assert_eq!(
+            encoder.try_verify_and_upgrade_spring_security_hash("wrongpassword", encoded_password),
+            Ok(Verification::Mismatch)
+        );
+
+        let verification = encoder.try_verify_and_upgrade_spring_security_hash("password", encoded_password).unwrap();
+        let upgraded_encoded_password = verification.upgraded_encoded_password().unwrap();
+
+        assert!(upgraded_encoded_password.starts_with("{bcrypt}$2a$10$"));
+        assert!(encoder.matches_spring_security_hash("password", upgraded_encoded_password));
+        assert_eq!(
+            encoder.try_verify_and_upgrade_spring_security_hash("password", upgraded_encoded_password),
+            Ok(Verification::Match)
+        );
+    }
+
+    #[test]
+    fn verify_and_upgrade_hash_of_default_encoder_for_matches() {
+        let mut encoder: DelegatingPasswordEncoder = Default::default();
+        encoder.set_default_password_encoder_for_matches(NoOpPasswordEncoder {});
+
+        let verification = encoder.try_verify_and_upgrade_spring_security_hash("password", "password").unwrap();
+
+        assert!(verification.upgraded_encoded_password().unwrap().starts_with("{bcrypt}"));
+    }
+
+    #[test]
+    fn builder_uses_custom_id_markers() {
+        let encoder = DelegatingPasswordEncoder::builder().with_id_prefix("$$").with_id_suffix("$$").build().unwrap();
+
+        let encoded_password = encoder.encode_spring_security_hash("password").unwrap();
+
+        assert!(encoded_password.starts_with("$$bcrypt$$$2a$10$"));
+        assert!(encoder.matches_spring_security_hash("password", &encoded_password));
+        assert!(encoder.matches_spring_security_hash("password", "$$MD5$${thisissalt}2a4e7104c2780098f50ed5a84bb2323d"));
+        assert_eq!(
+            encoder.try_matches_spring_security_hash("password", "{noop}password"),
+            Err(PasswordEncoderError::MissingEncoderId)
+        );
+    }
+
+    #[test]
+    fn builder_uses_given_encoders_and_id_for_encode() {
+        let mut encoders: HashMap<String, Box<dyn PasswordEncoder + Send + Sync>> = HashMap::new();
+        encoders.insert(String::from("noop"), Box::new(NoOpPasswordEncoder {}));
+        let encoder = DelegatingPasswordEncoder::builder()
+            .with_encoders(encoders)
+            .with_encoder("md5", Md5PasswordEncoder::default())
+            .with_id_for_encode("noop")
+            .with_id_prefix("")
+            .with_id_suffix(":")
+            .with_default_password_encoder_for_matches(NoOpPasswordEncoder {})
+            .build()
+            .unwrap();
+
+        assert_eq!(encoder.encode_spring_security_hash("password"), Some(String::from("noop:password")));
+        assert!(encoder.matches_spring_security_hash("password", "md5:{thisissalt}2a4e7104c2780098f50ed5a84bb2323d"));
+        assert!(!encoder.matches_spring_security_hash("password", "bcrypt:$2a$10$dXJ3SW6G7P50lGmMkkmwe.20cQQubK3.HZWzG3YB1tlRy.fqvM/BG"));
+        assert!(encoder.matches_spring_security_hash("password", "password"));
+    }
+
+    #[test]
+    fn builder_fails_for_invalid_markers() {
+        assert!(matches!(
+            DelegatingPasswordEncoder::builder().with_id_suffix("").build(),
+            Err(PasswordEncoderError::InvalidParameters)
+        ));
+        // SHA-1 and SHA-256 contain the suffix
+        assert!(matches!(
+            DelegatingPasswordEncoder::builder().with_id_suffix("-").build(),
+            Err(PasswordEncoderError::InvalidParameters)
+        ));
+        assert!(matches!(
+            DelegatingPasswordEncoder::builder().with_encoder("{noop}", NoOpPasswordEncoder {}).build(),
+            Err(PasswordEncoderError::InvalidParameters)
+        ));
+    }
+
+    #[test]
+    fn builder_fails_for_unmapped_id_for_encode() {
+        assert!(matches!(
+            DelegatingPasswordEncoder::builder().with_id_for_encode("unmapped").build(),
+            Err(PasswordEncoderError::UnmappedEncoderId(id)) if id == "unmapped"
+        ));
     }
 }
@@ -1,2 +1,337 @@
-#[derive(Default)]
-pub struct LdapShaPasswordEncoder {}
+use crate::codec::BASE64;
+use crate::error::PasswordEncoderError;
+use crate::keygen::secure_random_bytes;
+use crate::util::constant_time_equals;
+use crate::PasswordEncoder;
+use base64::Engine;
+use sha1::{Digest, Sha1};
+use zeroize::{Zeroize, Zeroizing};
+
+// https://github.com/spring-projects/spring-security/blob/0e3ee8990299436223c3037afb096f454f17ef5e/crypto/src/main/java/org/springframework/security/crypto/password/LdapShaPasswordEncoder.java#L49-L57
+pub(crate) const SHA_LENGTH: usize = 20;
+const SSHA_PREFIX: &str = "{SSHA}";
+const SSHA_PREFIX_LC: &str = "{ssha}";
+const SHA_PREFIX: &str = "{SHA}";
+const SHA_PREFIX_LC: &str = "{sha}";
+
+#[derive(Clone, Copy, Debug)]
+pub struct LdapShaPasswordEncoder {
+    salt_length: usize,
+    force_lower_case_prefix: bool,
+}
+
+impl Default for LdapShaPasswordEncoder {
+    fn default() -> LdapShaPasswordEncoder {
+        LdapShaPasswordEncoder {
+            // https://github.com/spring-projects/spring-security/blob/0e3ee8990299436223c3037afb096f454f17ef5e/crypto/src/main/java/org/springframework/security/crypto/keygen/KeyGenerators.java#L35
+            salt_length: 8,
+            force_lower_case_prefix: false,
+        }
+    }
+}
+
+impl LdapShaPasswordEncoder {
+    /// Encodes with the given length of random salt, a length of zero creates unsalted {SHA} hashes
+    pub fn new(salt_length: usize) -> LdapShaPasswordEncoder {
+        LdapShaPasswordEncoder {
+            salt_length,
+            force_lower_case_prefix: false,
+        }
+    }
+
+    pub fn set_force_lower_case_prefix(&mut self, force_lower_case_prefix: bool) {
+        self.force_lower_case_prefix = force_lower_case_prefix;
+    }
+
+    // returns the base64 of the digest with the salt appended, without any prefix
+    fn encode_without_prefix(&self, unencoded_password: &[u8], salt: &[u8]) -> String {
+        let mut hasher = Sha1::new();
+        hasher.update(unencoded_password);
+        hasher.update(salt);
+
+        let mut digest = hasher.finalize();
+        // reserve everything up front, growing would leave copies of the digest behind
+        let mut hash_and_salt = Zeroizing::new(Vec::with_capacity(digest.len() + salt.len()));
+        hash_and_salt.extend_from_slice(&digest);
+        hash_and_salt.extend_from_slice(salt);
+        digest.as_mut_slice().zeroize();
+
+        BASE64.encode(&hash_and_salt[..])
+    }
+
+    fn prefix_for_salt(&self, salt: &[u8]) -> String {
+        let prefix = match (salt.is_empty(), self.force_lower_case_prefix) {
+            (true, false) => SHA_PREFIX,
+            (true, true) => SHA_PREFIX_LC,
+            (false, false) => SSHA_PREFIX,
+            (false, true) => SSHA_PREFIX_LC,
+        };
+        prefix.to_string()
+    }
+}
+
+// nothing is returned for plaintext passwords, spring security uses the last closing brace and throws without one
+pub(crate) fn extract_prefix(encoded_password: &str) -> Result<Option<&str>, PasswordEncoderError> {
+    if !encoded_password.starts_with('{') {
+        return Ok(None);
+    }
+    match encoded_password.rfind('}') {
+        Some(position) => Ok(Some(&encoded_password[..=position])),
+        None => Err(PasswordEncoderError::MalformedHash),
+    }
+}
+
+// spring security accepts the prefixes in upper or lower case, but not in mixed case
+pub(crate) fn is_ssha_prefix(prefix: &str) -> bool {
+    prefix == SSHA_PREFIX || prefix == SSHA_PREFIX_LC
+}
+
+pub(crate) fn is_sha_prefix(prefix: &str) -> bool {
+    prefix == SHA_PREFIX || prefix == SHA_PREFIX_LC
+}
+
+pub(crate) fn extract_salt(encoded_password_without_prefix: &str) -> Option<Vec<u8>> {
+    let hash_and_salt = BASE64.decode(encoded_password_without_prefix).ok()?;
+    if hash_and_salt.len() < SHA_LENGTH {
+        return None;
+    }
+    Some(hash_and_salt[SHA_LENGTH..].to_vec())
+}
+
+impl PasswordEncoder for LdapShaPasswordEncoder {
+    fn matches_spring_security_hash(&self, unencoded_password: &str, encoded_password: &str) -> bool {
+        self.try_matches_spring_security_hash(unencoded_password, encoded_password).unwrap_or(false)
+    }
+
+    fn encode_spring_security_hash(&self, unencoded_password: &str) -> Option<String> {
+        self.try_encode_spring_security_hash(unencoded_password).ok()
+    }
+
+    fn try_matches_spring_security_hash_bytes(&self, unencoded_password: &[u8], encoded_password: &str) -> Result<bool, PasswordEncoderError> {
+        let prefix = match extract_prefix(encoded_password)? {
+            Some(prefix) => prefix,
+            // plaintext password without any prefix
+            None => return Ok(constant_time_equals(encoded_password.as_bytes(), unencoded_password)),
+        };
+        let encoded_password_without_prefix = &encoded_password[prefix.len()..];
+
+        let salt = if is_ssha_prefix(prefix) {
+            extract_salt(encoded_password_without_prefix).ok_or(PasswordEncoderError::MalformedHash)?
+        } else if is_sha_prefix(prefix) {
+            vec![]
+        } else {
+            return Err(PasswordEncoderError::UnsupportedAlgorithm(prefix.to_string()));
+        };
+
+        Ok(constant_time_equals(
+            encoded_password_without_prefix.as_bytes(),
+            self.encode_without_prefix(unencoded_password, &salt).as_bytes(),
+        ))
+    }
+
+    fn try_encode_spring_security_hash_bytes(&self, unencoded_password: &[u8]) -> Result<String, PasswordEncoderError> {
+        let salt = secure_random_bytes(self.salt_length)?;
+        Ok(self.prefix_for_salt(&salt) + &self.encode_without_prefix(unencoded_password, &salt))
+    }
+}
+
+#[cfg(test)]
+mod prefix_tests {
+    use super::{extract_prefix, is_sha_prefix, is_ssha_prefix};
+    use crate::error::PasswordEncoderError;
+
+    #[test]
+    fn finds_prefix() {
+        assert_eq!(extract_prefix("{SSHA}cGFzc3dvcmQ="), Ok(Some("{SSHA}")));
+    }
+
+    #[test]
+    fn finds_no_prefix_without_opening_brace() {
+        assert_eq!(extract_prefix("SSHA}cGFzc3dvcmQ="), Ok(None));
+    }
+
+    #[test]
+    fn error_for_prefix_without_closing_brace() {
+        assert_eq!(extract_prefix("{SSHAcGFzc3dvcmQ="), Err(PasswordEncoderError::MalformedHash));
+    }
+
+    #[test]
+    fn finds_prefix_up_to_last_closing_brace() {
+        assert_eq!(extract_prefix("{SSHA}}cGFzc3dvcmQ="), Ok(Some("{SSHA}}")));
+    }
+
+    #[test]
+    fn accepts_upper_and_lower_case_prefixes_only() {
+        assert!(is_ssha_prefix("{SSHA}"));
+        assert!(is_ssha_prefix("{ssha}"));
+        assert!(!is_ssha_prefix("{SsHa}"));
+        assert!(is_sha_prefix("{SHA}"));
+        assert!(is_sha_prefix("{sha}"));
+        assert!(!is_sha_prefix("{sHa}"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LdapShaPasswordEncoder, PasswordEncoder};
+    use crate::error::PasswordEncoderError;
+
+    // https://github.com/spring-projects/spring-security/blob/0e3ee8990299436223c3037afb096f454f17ef5e/crypto/src/test/java/org/springframework/security/crypto/password/LdapShaPasswordEncoderTests.java
+
+    #[test]
+    fn matches_sha_hash() {
+        let encoder: LdapShaPasswordEncoder = Default::default();
+
+        let unencoded_password = String::from("boabspasswurd");
+        let encoded_password = String::from("{SHA}ddSFGmjXYPbZC+NXR2kCzBRjqiE=");
+
+        assert!(encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+    }
+
+    #[test]
+    fn matches_lower_cased_sha_hash() {
+        let encoder: LdapShaPasswordEncoder = Default::default();
+
+        let unencoded_password = String::from("boabspasswurd");
+        let encoded_password = String::from("{sha}ddSFGmjXYPbZC+NXR2kCzBRjqiE=");
+
+        assert!(encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+    }
+
+    #[test]
+    fn matches_ssha_hash() {
+        let encoder: LdapShaPasswordEncoder = Default::default();
+
+        let unencoded_password = String::from("boabspasswurd");
+        let encoded_password = String::from("{SSHA}PQy2j+6n5ytA+YlAKkM8Fh4p6u2JxfVd");
+
+        assert!(encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+    }
+
+    #[test]
+    fn matches_lower_cased_ssha_hash() {
+        let encoder: LdapShaPasswordEncoder = Default::default();
+
+        let unencoded_password = String::from("boabspasswurd");
+        let encoded_password = String::from("{ssha}PQy2j+6n5ytA+YlAKkM8Fh4p6u2JxfVd");
+
+        assert!(encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+    }
+
+    #[test]
+    fn no_match_for_wrong_password() {
+        let encoder: LdapShaPasswordEncoder = Default::default();
+
+        let unencoded_password = String::from("wrongpassword");
+        let encoded_password = String::from("{SSHA}PQy2j+6n5ytA+YlAKkM8Fh4p6u2JxfVd");
+
+        assert!(!encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+    }
+
+    #[test]
+    fn matches_plaintext_without_prefix() {
+        let encoder: LdapShaPasswordEncoder = Default::default();
+
+        let unencoded_password = String::from("boabspasswurd");
+
+        assert!(encoder.matches_spring_security_hash(&unencoded_password, &unencoded_password));
+        assert!(!encoder.matches_spring_security_hash(&unencoded_password, &String::from("somethingelse")));
+    }
+
+    #[test]
+    fn no_match_for_unsupported_prefix() {
+        let encoder: LdapShaPasswordEncoder = Default::default();
+
+        let unencoded_password = String::from("boabspasswurd");
+        let encoded_password = String::from("{MD5}ddSFGmjXYPbZC+NXR2kCzBRjqiE=");
+
+        assert!(!encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+    }
+
+    #[test]
+    fn error_for_unsupported_prefix() {
+        let encoder: LdapShaPasswordEncoder = Default::default();
+
+        let result = encoder.try_matches_spring_security_hash("boabspasswurd", "{MD5}ddSFGmjXYPbZC+NXR2kCzBRjqiE=");
+
+        assert_eq!(result, Err(PasswordEncoderError::UnsupportedAlgorithm(String::from("{MD5}"))));
+    }
+
+    #[test]
+    fn error_for_mixed_case_prefix() {
+        let encoder: LdapShaPasswordEncoder = Default::default();
+
+        let result = encoder.try_matches_spring_security_hash("password", "{sHa}W6ph5Mm5Pz8GgiULbPgzG37mj9g=");
+
+        assert_eq!(result, Err(PasswordEncoderError::UnsupportedAlgorithm(String::from("{sHa}"))));
+    }
+
+    #[test]
+    fn error_for_prefix_without_closing_brace() {
+        let encoder: LdapShaPasswordEncoder = Default::default();
+
+        // not matched as plaintext
+        let result = encoder.try_matches_spring_security_hash("{SSHAcGFzc3dvcmQ=", "{SSHAcGFzc3dvcmQ=");
+
+        assert_eq!(result, Err(PasswordEncoderError::MalformedHash));
+    }
+
+    #[test]
+    fn error_for_malformed_ssha_hash() {
+        let encoder: LdapShaPasswordEncoder = Default::default();
+
+        assert_eq!(
+            encoder.try_matches_spring_security_hash("boabspasswurd", "{SSHA}not base64"),
+            Err(PasswordEncoderError::MalformedHash)
+        );
+        assert_eq!(
+            encoder.try_matches_spring_security_hash("wrongpassword", "{SSHA}PQy2j+6n5ytA+YlAKkM8Fh4p6u2JxfVd"),
+            Ok(false)
+        );
+    }
+
+    #[test]
+    fn no_match_for_too_short_ssha_hash() {
+        let encoder: LdapShaPasswordEncoder = Default::default();
+
+        let unencoded_password = String::from("boabspasswurd");
+        let encoded_password = String::from("{SSHA}cGFzc3dvcmQ=");
+
+        assert!(!encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+    }
+
+    #[test]
+    fn encode_with_defaults() {
+        let encoder: LdapShaPasswordEncoder = Default::default();
+
+        let unencoded_password = String::from("boabspasswurd");
+        let encoded_password = encoder.encode_spring_security_hash(&unencoded_password).unwrap();
+
+        assert!(encoded_password.starts_with("{SSHA}"));
+        // 20 bytes digest and 8 bytes salt
+        assert_eq!(encoded_password.len(), "{SSHA}".len() + 40);
+        assert!(encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+    }
+
+    #[test]
+    fn encode_with_forced_lower_case_prefix() {
+        let mut encoder: LdapShaPasswordEncoder = Default::default();
+        encoder.set_force_lower_case_prefix(true);
+
+        let unencoded_password = String::from("boabspasswurd");
+        let encoded_password = encoder.encode_spring_security_hash(&unencoded_password).unwrap();
+
+        assert!(encoded_password.starts_with("{ssha}"));
+        assert!(encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+    }
+
+    #[test]
+    fn encode_without_salt() {
+        let encoder = LdapShaPasswordEncoder::new(0);
+
+        let unencoded_password = String::from("boabspasswurd");
+        let encoded_password = encoder.encode_spring_security_hash(&unencoded_password).unwrap();
+
+        assert_eq!(encoded_password, "{SHA}ddSFGmjXYPbZC+NXR2kCzBRjqiE=");
+    }
+}
@@ -1,2 +1,569 @@
-#[derive(Default)]
-pub struct MessageDigestPasswordEncoder {}
+use crate::codec::BASE64;
+use crate::error::PasswordEncoderError;
+use crate::keygen::base64_string_key;
+use crate::util::constant_time_equals;
+use crate::PasswordEncoder;
+use base64::{DecodeSliceError, Engine};
+use digest::typenum::Unsigned;
+use digest::{Digest, Output, OutputSizeUser};
+use hex::FromHexError;
+use std::fmt;
+use std::marker::PhantomData;
+use zeroize::{Zeroize, Zeroizing};
+
+// password and salt up to this length are hashed without allocating
+const SALTED_PASSWORD_STACK_LENGTH: usize = 256;
+
+// longest supported digest (SHA-512), digests which are longer fail to compile
+const MAX_DIGEST_LENGTH: usize = 64;
+
+// base64 decoding needs up to 2 bytes more than the digest for its last block
+const MAX_DECODED_DIGEST_LENGTH: usize = MAX_DIGEST_LENGTH + 2;
+
+/// Generic version of o.s.s.c.password.MessageDigestPasswordEncoder, the digest replaces the algorithm name
+pub struct MessageDigestPasswordEncoder<D> {
+    salt_prefix: String,
+    salt_suffix: String,
+    salt_byte_size: u32,
+    iterations: u32,
+    encode_hash_as_base64: bool,
+    digest: PhantomData<fn() -> D>,
+}
+
+impl<D> Default for MessageDigestPasswordEncoder<D> {
+    fn default() -> MessageDigestPasswordEncoder<D> {
+        MessageDigestPasswordEncoder {
+            // https://github.com/spring-projects/spring-security/blob/0e3ee8990299436223c3037afb096f454f17ef5e/crypto/src/main/java/org/springframework/security/crypto/password/MessageDigestPasswordEncoder.java#L87
+            salt_prefix: String::from("{"),
+            salt_suffix: String::from("}"),
+            // https://github.com/spring-projects/spring-security/blob/a4e13c520b351c48378d0287167e53cfc581de46/crypto/src/main/java/org/springframework/security/crypto/keygen/Base64StringKeyGenerator.java#L31
+            salt_byte_size: 32,
+            iterations: 1,
+            encode_hash_as_base64: false,
+            digest: PhantomData,
+        }
+    }
+}
+
+impl<D> Clone for MessageDigestPasswordEncoder<D> {
+    fn clone(&self) -> MessageDigestPasswordEncoder<D> {
+        MessageDigestPasswordEncoder {
+            salt_prefix: self.salt_prefix.clone(),
+            salt_suffix: self.salt_suffix.clone(),
+            salt_byte_size: self.salt_byte_size,
+            iterations: self.iterations,
+            encode_hash_as_base64: self.encode_hash_as_base64,
+            digest: PhantomData,
+        }
+    }
+}
+
+impl<D> fmt::Debug for MessageDigestPasswordEncoder<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MessageDigestPasswordEncoder")
+            .field("salt_prefix", &self.salt_prefix)
+            .field("salt_suffix", &self.salt_suffix)
+            .field("salt_byte_size", &self.salt_byte_size)
+            .field("iterations", &self.iterations)
+            .field("encode_hash_as_base64", &self.encode_hash_as_base64)
+            .finish()
+    }
+}
+
+impl<D> MessageDigestPasswordEncoder<D> {
+    /// Amount of digest rounds, returns false and keeps the current amount for 0 iterations
+    // spring security throws an IllegalArgumentException for less than 1 iteration
+    // https://github.com/spring-projects/spring-security/blob/0e3ee8990299436223c3037afb096f454f17ef5e/crypto/src/main/java/org/springframework/security/crypto/password/Digester.java
+    pub fn set_iterations(&mut self, iterations: u32) -> bool {
+        if iterations == 0 {
+            return false;
+        }
+        self.iterations = iterations;
+        true
+    }
+
+    pub fn set_encode_hash_as_base64(&mut self, encode_hash_as_base64: bool) {
+        self.encode_hash_as_base64 = encode_hash_as_base64;
+    }
+
+    /// Fails for 0 iterations like `set_iterations`
+    pub fn with_iterations(mut self, iterations: u32) -> Result<Self, PasswordEncoderError> {
+        if !self.set_iterations(iterations) {
+            return Err(PasswordEncoderError::InvalidParameters);
+        }
+        Ok(self)
+    }
+
+    pub fn with_encode_hash_as_base64(mut self, encode_hash_as_base64: bool) -> Self {
+        self.set_encode_hash_as_base64(encode_hash_as_base64);
+        self
+    }
+
+    /// Marker in front of the salt, fails for an empty marker as every hash would look salted
+    pub fn with_salt_prefix(mut self, salt_prefix: &str) -> Result<Self, PasswordEncoderError> {
+        if salt_prefix.is_empty() {
+            return Err(PasswordEncoderError::InvalidParameters);
+        }
+        self.salt_prefix = salt_prefix.to_string();
+        Ok(self)
+    }
+
+    /// Marker behind the salt, must not be part of the generated base64 salt (like "+", "/" or "="),
+    /// fails for an empty marker
+    pub fn with_salt_suffix(mut self, salt_suffix: &str) -> Result<Self, PasswordEncoderError> {
+        if salt_suffix.is_empty() {
+            return Err(PasswordEncoderError::InvalidParameters);
+        }
+        self.salt_suffix = salt_suffix.to_string();
+        Ok(self)
+    }
+}
+
+pub(crate) fn extract_salt<'a>(salt_prefix: &str, salt_suffix: &str, encoded_password: &'a str) -> Option<&'a str> {
+    // salt is optional :(
+    // looks like we have salt ... but needs suffix
+    let encoded_password_without_prefix = encoded_password.strip_prefix(salt_prefix)?;
+    // get first position of suffix
+    encoded_password_without_prefix
+        .find(salt_suffix)
+        .map(|position| &encoded_password_without_prefix[..position])
+}
+
+#[cfg(test)]
+mod salt_tests {
+    use super::extract_salt;
+
+    #[test]
+    fn can_find_salt_with_proper_prefix_with_proper_suffix() {
+        let encoded_password_with_proper_format = String::from("{thisissalt}2a4e7104c2780098f50ed5a84bb2323d");
+        let prefix = String::from("{");
+        let suffix = String::from("}");
+
+        let found_salt = extract_salt(&prefix, &suffix, &encoded_password_with_proper_format);
+
+        assert!(found_salt.is_some());
+
+        assert_eq!(found_salt.unwrap(), String::from("thisissalt"));
+    }
+
+    #[test]
+    fn unable_to_find_salt_with_proper_prefix_but_missing_suffix() {
+        let encoded_password_with_proper_format = String::from("{thisissalt2a4e7104c2780098f50ed5a84bb2323d");
+        let prefix = String::from("{");
+        let suffix = String::from("}");
+
+        let found_salt = extract_salt(&prefix, &suffix, &encoded_password_with_proper_format);
+
+        assert!(found_salt.is_none());
+    }
+
+    #[test]
+    fn unable_to_find_salt_with_proper_suffix_but_missing_prefix() {
+        let encoded_password_with_proper_format = String::from("thisissalt}2a4e7104c2780098f50ed5a84bb2323d");
+        let prefix = String::from("{");
+        let suffix = String::from("}");
+
+        let found_salt = extract_salt(&prefix, &suffix, &encoded_password_with_proper_format);
+
+        assert!(found_salt.is_none());
+    }
+
+    #[test]
+    fn unable_to_find_salt_without_salt_markers() {
+        let encoded_password_with_proper_format = String::from("thisissalt2a4e7104c2780098f50ed5a84bb2323d");
+        let prefix = String::from("{");
+        let suffix = String::from("}");
+
+        let found_salt = extract_salt(&prefix, &suffix, &encoded_password_with_proper_format);
+
+        assert!(found_salt.is_none());
+    }
+
+    #[test]
+    fn can_find_salt_with_multiple_prefixes_with_proper_suffix() {
+        let encoded_password_with_proper_format = String::from("{{thisissalt}2a4e7104c2780098f50ed5a84bb2323d");
+        let prefix = String::from("{");
+        let suffix = String::from("}");
+
+        let found_salt = extract_salt(&prefix, &suffix, &encoded_password_with_proper_format);
+
+        assert!(found_salt.is_some());
+
+        assert_eq!(found_salt.unwrap(), String::from("{thisissalt"));
+    }
+
+    #[test]
+    fn can_find_salt_with_multiple_suffixes_with_proper_prefix() {
+        let encoded_password_with_proper_format = String::from("{thisissalt}}2a4e7104c2780098f50ed5a84bb2323d");
+        let prefix = String::from("{");
+        let suffix = String::from("}");
+
+        let found_salt = extract_salt(&prefix, &suffix, &encoded_password_with_proper_format);
+
+        assert!(found_salt.is_some());
+
+        assert_eq!(found_salt.unwrap(), String::from("thisissalt"));
+    }
+
+    #[test]
+    fn can_find_salt_with_multiple_suffixes_with_multiple_prefixes() {
+        let encoded_password_with_proper_format = String::from("{{thisissalt}}2a4e7104c2780098f50ed5a84bb2323d");
+        let prefix = String::from("{");
+        let suffix = String::from("}");
+
+        let found_salt = extract_salt(&prefix, &suffix, &encoded_password_with_proper_format);
+
+        assert!(found_salt.is_some());
+
+        assert_eq!(found_salt.unwrap(), String::from("{thisissalt"));
+    }
+
+    #[test]
+    fn can_find_salt_with_same_prefix_and_suffix() {
+        let encoded_password_with_proper_format = String::from("$thisissalt$2a4e7104c2780098f50ed5a84bb2323d");
+        let prefix = String::from("$");
+        let suffix = String::from("$");
+
+        let found_salt = extract_salt(&prefix, &suffix, &encoded_password_with_proper_format);
+
+        assert_eq!(found_salt, Some("thisissalt"));
+    }
+}
+
+// https://github.com/spring-projects/spring-security/blob/0e3ee8990299436223c3037afb096f454f17ef5e/crypto/src/main/java/org/springframework/security/crypto/password/Digester.java#L66
+// digests the first round again for the remaining iterations, in place so no intermediate digest is left behind
+fn iterate_digest<D: Digest>(digest: &mut Output<D>, iterations: u32) {
+    for _ in 1..iterations {
+        let mut hasher = D::new();
+        hasher.update(&*digest);
+        hasher.finalize_into(digest);
+    }
+}
+
+pub(crate) fn iterated_digest<D: Digest>(value: &[u8], iterations: u32) -> Zeroizing<Vec<u8>> {
+    let mut digest = D::digest(value);
+    iterate_digest::<D>(&mut digest, iterations);
+    let result = Zeroizing::new(digest.to_vec());
+    digest.as_mut_slice().zeroize();
+    result
+}
+
+impl<D: Digest> MessageDigestPasswordEncoder<D> {
+    // evaluated when decode_digest gets compiled for a digest, so a longer digest can not overflow the decoding buffer
+    const DIGEST_FITS_DECODING_BUFFER: () = assert!(<D as OutputSizeUser>::OutputSize::USIZE <= MAX_DIGEST_LENGTH, "digest too long");
+
+    // the salt gets hashed including its markers, callers have to zeroize the returned digest
+    //
+    // password and salt are put together in a buffer which gets wiped, it stays on the stack unless they are longer than
+    // SALTED_PASSWORD_STACK_LENGTH. The hashers of the digest crates can not be wiped, so the tail of the input stays in
+    // their block buffer, like for every digest based encoder.
+    fn digest_with_salt(&self, unencoded_password: &[u8], salt: Option<&str>) -> Output<D> {
+        let parts: [&[u8]; 4] = match salt {
+            Some(found_salt) => [unencoded_password, self.salt_prefix.as_bytes(), found_salt.as_bytes(), self.salt_suffix.as_bytes()],
+            None => [unencoded_password, &[], &[], &[]],
+        };
+        let length: usize = parts.iter().map(|part| part.len()).sum();
+
+        let mut digest = if length <= SALTED_PASSWORD_STACK_LENGTH {
+            let mut password_to_hash = Zeroizing::new([0u8; SALTED_PASSWORD_STACK_LENGTH]);
+            let mut position = 0;
+            for part in parts {
+                password_to_hash[position..position + part.len()].copy_from_slice(part);
+                position += part.len();
+            }
+            D::digest(&password_to_hash[..length])
+        } else {
+            // reserve everything up front, growing would leave copies of the password behind
+            let mut password_to_hash = Zeroizing::new(Vec::with_capacity(length));
+            for part in parts {
+                password_to_hash.extend_from_slice(part);
+            }
+            D::digest(&password_to_hash[..])
+        };
+        iterate_digest::<D>(&mut digest, self.iterations);
+        digest
+    }
+
+    // decodes on the stack, nothing is returned for a well-formed digest of another length
+    fn decode_digest(&self, encoded_digest: &str) -> Result<Option<Output<D>>, PasswordEncoderError> {
+        let () = Self::DIGEST_FITS_DECODING_BUFFER;
+        let mut digest = Output::<D>::default();
+        if self.encode_hash_as_base64 {
+            let mut decoded = [0u8; MAX_DECODED_DIGEST_LENGTH];
+            return match BASE64.decode_slice(encoded_digest, &mut decoded) {
+                Ok(length) if length == digest.len() => {
+                    digest.copy_from_slice(&decoded[..length]);
+                    Ok(Some(digest))
+                }
+                Ok(_) | Err(DecodeSliceError::OutputSliceTooSmall) => Ok(None),
+                Err(DecodeSliceError::DecodeError(_)) => Err(PasswordEncoderError::MalformedHash),
+            };
+        }
+        match hex::decode_to_slice(encoded_digest, &mut digest) {
+            Ok(()) => Ok(Some(digest)),
+            Err(FromHexError::InvalidStringLength) if encoded_digest.bytes().all(|c| c.is_ascii_hexdigit()) => Ok(None),
+            Err(_) => Err(PasswordEncoderError::MalformedHash),
+        }
+    }
+
+    fn encode_digest(&self, digest: &[u8]) -> String {
+        if self.encode_hash_as_base64 {
+            return BASE64.encode(digest);
+        }
+        hex::encode(digest)
+    }
+}
+
+impl<D: Digest> PasswordEncoder for MessageDigestPasswordEncoder<D> {
+    fn matches_spring_security_hash(&self, unencoded_password: &str, encoded_password: &str) -> bool {
+        self.try_matches_spring_security_hash(unencoded_password, encoded_password).unwrap_or(false)
+    }
+
+    fn encode_spring_security_hash(&self, unencoded_password: &str) -> Option<String> {
+        self.try_encode_spring_security_hash(unencoded_password).ok()
+    }
+
+    fn try_matches_spring_security_hash_bytes(&self, unencoded_password: &[u8], encoded_password: &str) -> Result<bool, PasswordEncoderError> {
+        let salt = extract_salt(&self.salt_prefix, &self.salt_suffix, encoded_password);
+        let mut encoded_password_to_compare_against = encoded_password;
+
+        if let Some(found_salt) = salt {
+            // strip salt from encoded_password
+            encoded_password_to_compare_against = &encoded_password[(self.salt_prefix.len() + found_salt.len() + self.salt_suffix.len())..];
+        }
+
+        let stored_digest = match self.decode_digest(encoded_password_to_compare_against)? {
+            Some(stored_digest) => stored_digest,
+            None => return Ok(false),
+        };
+        let mut digest = self.digest_with_salt(unencoded_password, salt);
+        let matches = constant_time_equals(&stored_digest, &digest);
+        digest.as_mut_slice().zeroize();
+        Ok(matches)
+    }
+
+    fn try_encode_spring_security_hash_bytes(&self, unencoded_password: &[u8]) -> Result<String, PasswordEncoderError> {
+        let salt = base64_string_key(self.salt_byte_size as usize)?;
+        let mut digest = self.digest_with_salt(unencoded_password, Some(&salt));
+        let encoded_digest = self.encode_digest(&digest);
+        digest.as_mut_slice().zeroize();
+
+        Ok(self.salt_prefix.clone() + &salt + &self.salt_suffix + &encoded_digest)
+    }
+}
+
+/// Legacy encoders whose digests can be recomputed from the salt stored in front of them
+pub trait LegacyDigestEncoder: PasswordEncoder {
+    /// Splits an encoded password into the salt including its markers and the rest, the salt is empty for unsalted hashes
+    fn split_salt<'a>(&self, encoded_password: &'a str) -> (&'a str, &'a str);
+
+    /// Encodes the digest of the password with a salt as split off by `split_salt`, without the salt in front
+    fn encode_digest_with_salt(&self, unencoded_password: &[u8], salt: &str) -> Zeroizing<String>;
+
+    /// Decodes a stored digest and encodes it again, so it is written exactly like `encode_digest_with_salt` writes it
+    fn normalize_digest(&self, encoded_digest: &str) -> Result<Zeroizing<String>, PasswordEncoderError>;
+}
+
+impl<D: Digest> LegacyDigestEncoder for MessageDigestPasswordEncoder<D> {
+    fn split_salt<'a>(&self, encoded_password: &'a str) -> (&'a str, &'a str) {
+        match extract_salt(&self.salt_prefix, &self.salt_suffix, encoded_password) {
+            Some(found_salt) => encoded_password.split_at(self.salt_prefix.len() + found_salt.len() + self.salt_suffix.len()),
+            None => ("", encoded_password),
+        }
+    }
+
+    fn encode_digest_with_salt(&self, unencoded_password: &[u8], salt: &str) -> Zeroizing<String> {
+        let mut digest = self.digest_with_salt(unencoded_password, extract_salt(&self.salt_prefix, &self.salt_suffix, salt));
+        let encoded_digest = Zeroizing::new(self.encode_digest(&digest));
+        digest.as_mut_slice().zeroize();
+        encoded_digest
+    }
+
+    fn normalize_digest(&self, encoded_digest: &str) -> Result<Zeroizing<String>, PasswordEncoderError> {
+        // a digest of another length would never match
+        let digest = self.decode_digest(encoded_digest)?.ok_or(PasswordEncoderError::MalformedHash)?;
+        Ok(Zeroizing::new(self.encode_digest(&digest)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MessageDigestPasswordEncoder, PasswordEncoder};
+    use crate::error::PasswordEncoderError;
+    use sha1::Sha1;
+    use sha2::{Sha256, Sha512};
+
+    #[test]
+    fn matches_sha1_with_salt() {
+        let encoder: MessageDigestPasswordEncoder<Sha1> = Default::default();
+
+        let unencoded_password = String::from("boabspasswurd");
+        let encoded_password = String::from("{THIS_IS_A_SALT}38588edc07fa051246b64aaf6e0f2d44265e5a72");
+
+        assert!(encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+    }
+
+    #[test]
+    fn matches_sha256_with_salt() {
+        let encoder: MessageDigestPasswordEncoder<Sha256> = Default::default();
+
+        let unencoded_password = String::from("boabspasswurd");
+        let encoded_password = String::from("{THIS_IS_A_SALT}ce6040080dfd291e3b898281c4e761c7f6f77d1e85bec167410289702545fd06");
+
+        assert!(encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+    }
+
+    #[test]
+    fn matches_longest_digest_as_base64() {
+        let encoder = MessageDigestPasswordEncoder::<Sha512>::default().with_encode_hash_as_base64(true);
+
+        let encoded_password = encoder.encode_spring_security_hash("password").unwrap();
+
+        assert!(encoder.matches_spring_security_hash("password", &encoded_password));
+        assert!(!encoder.matches_spring_security_hash("wrongpassword", &encoded_password));
+    }
+
+    #[test]
+    fn matches_sha256_with_salt_as_base64() {
+        let mut encoder: MessageDigestPasswordEncoder<Sha256> = Default::default();
+        encoder.set_encode_hash_as_base64(true);
+
+        let unencoded_password = String::from("boabspasswurd");
+        let encoded_password = String::from("{THIS_IS_A_SALT}zmBACA39KR47iYKBxOdhx/b3fR6FvsFnQQKJcCVF/QY=");
+
+        assert!(encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+    }
+
+    #[test]
+    fn rejects_zero_iterations() {
+        let mut encoder: MessageDigestPasswordEncoder<Sha256> = Default::default();
+
+        assert!(!encoder.set_iterations(0));
+        assert!(encoder.set_iterations(2));
+        assert!(!encoder.set_iterations(0));
+
+        let encoded_password = String::from("{THIS_IS_A_SALT}004cd0d52c06a08b34a99f23f108ae082347ce5a3b9b8bb85338c306577eab8e");
+        assert!(encoder.matches_spring_security_hash("boabspasswurd", &encoded_password));
+    }
+
+    #[test]
+    fn builders_reject_invalid_values() {
+        let encoder = MessageDigestPasswordEncoder::<Sha256>::default;
+
+        assert_eq!(encoder().with_iterations(0).err(), Some(PasswordEncoderError::InvalidParameters));
+        assert_eq!(encoder().with_salt_prefix("").err(), Some(PasswordEncoderError::InvalidParameters));
+        assert_eq!(encoder().with_salt_suffix("").err(), Some(PasswordEncoderError::InvalidParameters));
+        assert!(encoder().with_iterations(2).is_ok());
+    }
+
+    #[test]
+    fn matches_sha256_with_iterations() {
+        let mut encoder: MessageDigestPasswordEncoder<Sha256> = Default::default();
+        encoder.set_iterations(2);
+
+        let unencoded_password = String::from("boabspasswurd");
+        let encoded_password = String::from("{THIS_IS_A_SALT}004cd0d52c06a08b34a99f23f108ae082347ce5a3b9b8bb85338c306577eab8e");
+
+        assert!(encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+    }
+
+    #[test]
+    fn no_match_for_wrong_password() {
+        let encoder: MessageDigestPasswordEncoder<Sha256> = Default::default();
+
+        let unencoded_password = String::from("wrongpassword");
+        let encoded_password = String::from("{THIS_IS_A_SALT}ce6040080dfd291e3b898281c4e761c7f6f77d1e85bec167410289702545fd06");
+
+        assert!(!encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+    }
+
+    #[test]
+    fn no_match_for_wrong_digest() {
+        let encoder: MessageDigestPasswordEncoder<Sha1> = Default::default();
+
+        let unencoded_password = String::from("boabspasswurd");
+        let encoded_password = String::from("{THIS_IS_A_SALT}ce6040080dfd291e3b898281c4e761c7f6f77d1e85bec167410289702545fd06");
+
+        assert!(!encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+    }
+
+    #[test]
+    fn no_match_for_digest_of_other_length() {
+        let encoder: MessageDigestPasswordEncoder<Sha1> = Default::default();
+
+        assert_eq!(encoder.try_matches_spring_security_hash("boabspasswurd", "{THIS_IS_A_SALT}ce6040"), Ok(false));
+        assert_eq!(encoder.try_matches_spring_security_hash("boabspasswurd", "{THIS_IS_A_SALT}"), Ok(false));
+    }
+
+    #[test]
+    fn no_match_for_base64_digest_of_other_length() {
+        let encoder: MessageDigestPasswordEncoder<Sha1> = MessageDigestPasswordEncoder::default().with_encode_hash_as_base64(true);
+
+        let too_long_digest = "zmBACA39KR47iYKBxOdhx/b3fR6FvsFnQQKJcCVF/QY=".repeat(4);
+
+        assert_eq!(
+            encoder.try_matches_spring_security_hash("boabspasswurd", "{THIS_IS_A_SALT}zmBACA39KR47iYKBxOdhx/b3fR6FvsFnQQKJcCVF/QY="),
+            Ok(false)
+        );
+        assert_eq!(
+            encoder.try_matches_spring_security_hash("boabspasswurd", &format!("{{THIS_IS_A_SALT}}{}", too_long_digest)),
+            Ok(false)
+        );
+    }
+
+    #[test]
+    fn error_for_malformed_digest() {
+        let encoder: MessageDigestPasswordEncoder<Sha256> = Default::default();
+        let base64_encoder: MessageDigestPasswordEncoder<Sha256> = MessageDigestPasswordEncoder::default().with_encode_hash_as_base64(true);
+
+        assert_eq!(
+            encoder.try_matches_spring_security_hash("boabspasswurd", "{THIS_IS_A_SALT}not hex"),
+            Err(PasswordEncoderError::MalformedHash)
+        );
+        assert_eq!(
+            encoder.try_matches_spring_security_hash("boabspasswurd", "{THIS_IS_A_SALT}ce6"),
+            Err(PasswordEncoderError::MalformedHash)
+        );
+        assert_eq!(
+            base64_encoder.try_matches_spring_security_hash("boabspasswurd", "{THIS_IS_A_SALT}not base64"),
+            Err(PasswordEncoderError::MalformedHash)
+        );
+    }
+
+    #[test]
+    fn matches_password_longer_than_stack_buffer() {
+        let encoder: MessageDigestPasswordEncoder<Sha256> = Default::default();
+
+        let unencoded_password = "boabspasswurd".repeat(30);
+        let encoded_password = encoder.encode_spring_security_hash(&unencoded_password).unwrap();
+
+        assert!(encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+        assert!(!encoder.matches_spring_security_hash("boabspasswurd", &encoded_password));
+    }
+
+    #[test]
+    fn encode_sha256() {
+        let encoder: MessageDigestPasswordEncoder<Sha256> = Default::default();
+
+        let unencoded_password = String::from("boabspasswurd");
+        let encoded_password = encoder.encode_spring_security_hash(&unencoded_password).unwrap();
+
+        // 44 chars of salt with markers and 64 chars of hex
+        assert_eq!(encoded_password.len(), 46 + 64);
+        assert!(encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+    }
+
+    #[test]
+    fn encode_sha1_as_base64_with_iterations() {
+        let mut encoder: MessageDigestPasswordEncoder<Sha1> = Default::default();
+        encoder.set_encode_hash_as_base64(true);
+        encoder.set_iterations(3);
+
+        let unencoded_password = String::from("boabspasswurd");
+        let encoded_password = encoder.encode_spring_security_hash(&unencoded_password).unwrap();
+
+        // 44 chars of salt with markers and 28 chars of base64
+        assert_eq!(encoded_password.len(), 46 + 28);
+        assert!(encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+    }
+}
@@ -1,211 +1,173 @@
-use crate::PasswordEncoder;
-use hex::decode;
-use md5::{Digest, Md5};
-
-#[derive(Debug)]
-pub struct Md5PasswordEncoder {
-    salt_prefix: String,
-    salt_suffix: String,
-    salt_byte_size: u32,
-}
+use crate::encoder::md::MessageDigestPasswordEncoder;
+use md5::Md5;
 
-impl Default for Md5PasswordEncoder {
-    fn default() -> Md5PasswordEncoder {
-        Md5PasswordEncoder {
-            // https://github.com/spring-projects/spring-security/blob/0e3ee8990299436223c3037afb096f454f17ef5e/crypto/src/main/java/org/springframework/security/crypto/password/MessageDigestPasswordEncoder.java#L87
-            salt_prefix: String::from("{"),
-            salt_suffix: String::from("}"),
-            // https://github.com/spring-projects/spring-security/blob/a4e13c520b351c48378d0287167e53cfc581de46/crypto/src/main/java/org/springframework/security/crypto/keygen/Base64StringKeyGenerator.java#L31
-            salt_byte_size: 32,
-        }
-    }
-}
-
-fn extract_salt(salt_prefix: &String, salt_suffix: &String, encoded_password: &String) -> Option<String> {
-    // salt is optional :(
-    let prefix_length = salt_prefix.len();
-    if encoded_password.starts_with(salt_prefix) {
-        // looks like we have salt ... but needs suffix
-        if !encoded_password.contains(salt_suffix) {
-            // no salt
-        }
-        // get first position of suffix
-        let position_of_suffix = encoded_password.find(salt_suffix);
-        if position_of_suffix.is_some() {
-            // finally extract salt
-            let position = position_of_suffix.unwrap();
-            let salt = &encoded_password.clone().into_bytes()[prefix_length..position];
-            return Some(String::from_utf8(salt.to_vec()).unwrap());
-        }
-    }
-    None
-}
+// https://github.com/spring-projects/spring-security/blob/0e3ee8990299436223c3037afb096f454f17ef5e/crypto/src/main/java/org/springframework/security/crypto/password/MessageDigestPasswordEncoder.java
+pub type Md5PasswordEncoder = MessageDigestPasswordEncoder<Md5>;
 
 #[cfg(test)]
-mod salt_tests {
-    use super::extract_salt;
+mod tests {
+    use super::Md5PasswordEncoder;
+    use crate::error::PasswordEncoderError;
+    use crate::PasswordEncoder;
 
     #[test]
-    fn can_find_salt_with_proper_prefix_with_proper_suffix() {
-        let encoded_password_with_proper_format = String::from("{thisissalt}2a4e7104c2780098f50ed5a84bb2323d");
-        let prefix = String::from("{");
-        let suffix = String::from("}");
+    fn matches_correct_password_with_salt() {
+        let encoder: Md5PasswordEncoder = Default::default();
 
-        let found_salt = extract_salt(&prefix, &suffix, &encoded_password_with_proper_format);
+        let unencoded_password = String::from("password");
+        let encoded_password = String::from("{thisissalt}2a4e7104c2780098f50ed5a84bb2323d");
 
-        assert!(found_salt.is_some());
+        let result = encoder.matches_spring_security_hash(&unencoded_password, &encoded_password);
 
-        assert_eq!(found_salt.unwrap(), String::from("thisissalt"));
+        assert!(result);
     }
 
     #[test]
-    fn unable_to_find_salt_with_proper_prefix_but_missing_suffix() {
-        let encoded_password_with_proper_format = String::from("{thisissalt2a4e7104c2780098f50ed5a84bb2323d");
-        let prefix = String::from("{");
-        let suffix = String::from("}");
-
-        let found_salt = extract_salt(&prefix, &suffix, &encoded_password_with_proper_format);
+    fn error_for_malformed_hex() {
+        let encoder: Md5PasswordEncoder = Default::default();
 
-        assert!(found_salt.is_none());
+        assert_eq!(
+            encoder.try_matches_spring_security_hash("password", "{thisissalt}not hex"),
+            Err(PasswordEncoderError::MalformedHash)
+        );
+        assert_eq!(
+            encoder.try_matches_spring_security_hash("wrongpassword", "{thisissalt}2a4e7104c2780098f50ed5a84bb2323d"),
+            Ok(false)
+        );
     }
 
     #[test]
-    fn unable_to_find_salt_with_proper_suffix_but_missing_prefix() {
-        let encoded_password_with_proper_format = String::from("thisissalt}2a4e7104c2780098f50ed5a84bb2323d");
-        let prefix = String::from("{");
-        let suffix = String::from("}");
+    fn matches_non_utf8_password_bytes() {
+        let encoder: Md5PasswordEncoder = Default::default();
 
-        let found_salt = extract_salt(&prefix, &suffix, &encoded_password_with_proper_format);
+        let encoded_password = "{thisissalt}627d48915485bb63ff73f2d7879eb6a3";
 
-        assert!(found_salt.is_none());
+        assert_eq!(encoder.try_matches_spring_security_hash_bytes(b"\xffpassword", encoded_password), Ok(true));
+        assert_eq!(encoder.try_matches_spring_security_hash_bytes(b"password", encoded_password), Ok(false));
     }
 
     #[test]
-    fn unable_to_find_salt_without_salt_markers() {
-        let encoded_password_with_proper_format = String::from("thisissalt2a4e7104c2780098f50ed5a84bb2323d");
-        let prefix = String::from("{");
-        let suffix = String::from("}");
+    fn matches_correct_password_no_salt() {
+        let encoder: Md5PasswordEncoder = Default::default();
+
+        let unencoded_password = String::from("password");
+        let encoded_password = String::from("5f4dcc3b5aa765d61d8327deb882cf99");
 
-        let found_salt = extract_salt(&prefix, &suffix, &encoded_password_with_proper_format);
+        let result = encoder.matches_spring_security_hash(&unencoded_password, &encoded_password);
 
-        assert!(found_salt.is_none());
+        assert!(result);
     }
 
     #[test]
-    fn can_find_salt_with_multiple_prefixes_with_proper_suffix() {
-        let encoded_password_with_proper_format = String::from("{{thisissalt}2a4e7104c2780098f50ed5a84bb2323d");
-        let prefix = String::from("{");
-        let suffix = String::from("}");
+    fn matches_correct_password_no_salt_uppercased() {
+        let encoder: Md5PasswordEncoder = Default::default();
 
-        let found_salt = extract_salt(&prefix, &suffix, &encoded_password_with_proper_format);
+        let unencoded_password = String::from("password");
+        let encoded_password = String::from("5F4DCC3B5AA765D61D8327DEB882CF99");
 
-        assert!(found_salt.is_some());
+        let result = encoder.matches_spring_security_hash(&unencoded_password, &encoded_password);
 
-        assert_eq!(found_salt.unwrap(), String::from("{thisissalt"));
+        assert!(result);
     }
 
     #[test]
-    fn can_find_salt_with_multiple_suffixes_with_proper_prefix() {
-        let encoded_password_with_proper_format = String::from("{thisissalt}}2a4e7104c2780098f50ed5a84bb2323d");
-        let prefix = String::from("{");
-        let suffix = String::from("}");
+    fn encode_with_generated_salt() {
+        let encoder: Md5PasswordEncoder = Default::default();
 
-        let found_salt = extract_salt(&prefix, &suffix, &encoded_password_with_proper_format);
+        let unencoded_password = String::from("password");
+        let encoded_password = encoder.encode_spring_security_hash(&unencoded_password).unwrap();
 
-        assert!(found_salt.is_some());
+        // {salt}hexdigest with 32 bytes of base64 encoded salt and a 16 bytes digest
+        assert!(encoded_password.starts_with('{'));
+        assert_eq!(encoded_password.find('}'), Some(45));
+        assert_eq!(encoded_password.len(), 46 + 32);
+        assert!(encoded_password[46..].chars().all(|c| c.is_ascii_hexdigit()));
 
-        assert_eq!(found_salt.unwrap(), String::from("thisissalt"));
+        assert!(encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
     }
 
     #[test]
-    fn can_find_salt_with_multiple_suffixes_with_multiple_prefixes() {
-        let encoded_password_with_proper_format = String::from("{{thisissalt}}2a4e7104c2780098f50ed5a84bb2323d");
-        let prefix = String::from("{");
-        let suffix = String::from("}");
-
-        let found_salt = extract_salt(&prefix, &suffix, &encoded_password_with_proper_format);
+    fn encode_generates_different_salt_each_time() {
+        let encoder: Md5PasswordEncoder = Default::default();
 
-        assert!(found_salt.is_some());
+        let unencoded_password = String::from("password");
 
-        assert_eq!(found_salt.unwrap(), String::from("{thisissalt"));
+        assert_ne!(
+            encoder.encode_spring_security_hash(&unencoded_password).unwrap(),
+            encoder.encode_spring_security_hash(&unencoded_password).unwrap()
+        );
     }
-}
 
-impl PasswordEncoder for Md5PasswordEncoder {
-    fn matches_spring_security_hash(&self, unencoded_password: &String, encoded_password: &String) -> bool {
-        let salt = extract_salt(&self.salt_prefix, &self.salt_suffix, &encoded_password);
-        let mut password_to_hash = String::from(unencoded_password);
-        let mut encoded_password_to_compare_against = String::from(encoded_password);
-
-        if salt.is_some() {
-            let found_salt = salt.unwrap();
-            password_to_hash.push_str(&self.salt_prefix);
-            password_to_hash.push_str(found_salt.as_str());
-            password_to_hash.push_str(&self.salt_suffix);
-            // strip salt from encoded_password
-            encoded_password_to_compare_against = encoded_password[(&self.salt_prefix.len() + found_salt.as_str().len() + &self.salt_suffix.len())..].to_string();
-        }
-
-        let mut hasher = Md5::new();
-        hasher.update(password_to_hash.as_bytes());
-        let md5_hash_bytes = hasher.finalize();
-
-        return match decode(encoded_password_to_compare_against) {
-            Ok(encoded_password_string) => {
-                // let md5_bytes = &md5_hash_bytes[..];
-                // println!("{:?}", &md5_bytes);
-                // println!("{:?}", &encoded_password_string);
-                // encoded_password_string == md5_bytes
-                encoded_password_string == &md5_hash_bytes[..]
-            }
-            Err(err) => false,
-        };
-    }
+    #[test]
+    fn matches_with_iterations() {
+        let encoder = Md5PasswordEncoder::default().with_iterations(3).unwrap();
 
-    fn encode_spring_security_hash(&self, unencoded_password: &String) -> Option<String> {
-        // TODO find easy way to generated salt
-        None
-    }
-}
+        let unencoded_password = String::from("password");
+        let encoded_password = String::from("{thisissalt}7bb197e2fdf8179d7b89b4b034b5dcb1");
 
-#[cfg(test)]
-mod tests {
-    use super::Md5PasswordEncoder;
-    use super::PasswordEncoder;
+        assert!(encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+    }
 
     #[test]
-    fn matches_correct_password_with_salt() {
-        let encoder: Md5PasswordEncoder = Default::default();
+    fn matches_base64_encoded_hash() {
+        let encoder = Md5PasswordEncoder::default().with_encode_hash_as_base64(true);
 
         let unencoded_password = String::from("password");
-        let encoded_password = String::from("{thisissalt}2a4e7104c2780098f50ed5a84bb2323d");
+        let encoded_password = String::from("{thisissalt}Kk5xBMJ4AJj1DtWoS7IyPQ==");
 
-        let result = encoder.matches_spring_security_hash(&unencoded_password, &encoded_password);
+        assert!(encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
 
-        assert!(result);
+        // hex is not valid base64 for this digest length
+        assert!(!encoder.matches_spring_security_hash(&unencoded_password, &String::from("{thisissalt}2a4e7104c2780098f50ed5a84bb2323d")));
     }
 
     #[test]
-    fn matches_correct_password_no_salt() {
-        let encoder: Md5PasswordEncoder = Default::default();
+    fn matches_with_custom_salt_markers() {
+        let encoder = Md5PasswordEncoder::default().with_salt_prefix("[").unwrap().with_salt_suffix("]").unwrap();
 
         let unencoded_password = String::from("password");
-        let encoded_password = String::from("5f4dcc3b5aa765d61d8327deb882cf99");
+        let encoded_password = String::from("[thisissalt]37694f6a5a6953f765ff91a9dd783950");
 
-        let result = encoder.matches_spring_security_hash(&unencoded_password, &encoded_password);
+        assert!(encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+    }
 
-        assert!(result);
+    #[test]
+    fn matches_with_all_options() {
+        let encoder = Md5PasswordEncoder::default()
+            .with_iterations(1024)
+            .unwrap()
+            .with_encode_hash_as_base64(true)
+            .with_salt_prefix("$$")
+            .unwrap()
+            .with_salt_suffix("$$")
+            .unwrap();
+
+        let unencoded_password = String::from("password");
+        let encoded_password = String::from("$$thisissalt$$RZmrbk1J2VTe5IelanihSw==");
+
+        assert!(encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
     }
 
     #[test]
-    fn matches_correct_password_no_salt_uppercased() {
-        let encoder: Md5PasswordEncoder = Default::default();
+    fn encode_with_all_options() {
+        let encoder = Md5PasswordEncoder::default()
+            .with_iterations(1024)
+            .unwrap()
+            .with_encode_hash_as_base64(true)
+            .with_salt_prefix("[")
+            .unwrap()
+            .with_salt_suffix("]")
+            .unwrap();
 
         let unencoded_password = String::from("password");
-        let encoded_password = String::from("5F4DCC3B5AA765D61D8327DEB882CF99");
+        let encoded_password = encoder.encode_spring_security_hash(&unencoded_password).unwrap();
 
-        let result = encoder.matches_spring_security_hash(&unencoded_password, &encoded_password);
+        assert!(encoded_password.starts_with('['));
+        assert_eq!(encoded_password.find(']'), Some(45));
+        // base64 of 16 bytes digest
+        assert_eq!(encoded_password.len(), 46 + 24);
 
-        assert!(result);
+        assert!(encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+        assert!(!Md5PasswordEncoder::default().matches_spring_security_hash(&unencoded_password, &encoded_password));
     }
 }
@@ -1,15 +1,26 @@
+use crate::error::PasswordEncoderError;
+use crate::util::constant_time_equals;
 use crate::PasswordEncoder;
 
 #[derive(Debug, Default)]
 pub struct NoOpPasswordEncoder;
 
 impl PasswordEncoder for NoOpPasswordEncoder {
-    fn matches_spring_security_hash(&self, unencoded_password: &String, encoded_password: &String) -> bool {
-        unencoded_password.eq(encoded_password)
+    fn matches_spring_security_hash(&self, unencoded_password: &str, encoded_password: &str) -> bool {
+        self.try_matches_spring_security_hash(unencoded_password, encoded_password).unwrap_or(false)
     }
 
-    fn encode_spring_security_hash(&self, unencoded_password: &String) -> Option<String> {
-        Some(unencoded_password.clone())
+    fn encode_spring_security_hash(&self, unencoded_password: &str) -> Option<String> {
+        self.try_encode_spring_security_hash(unencoded_password).ok()
+    }
+
+    fn try_matches_spring_security_hash_bytes(&self, unencoded_password: &[u8], encoded_password: &str) -> Result<bool, PasswordEncoderError> {
+        Ok(constant_time_equals(encoded_password.as_bytes(), unencoded_password))
+    }
+
+    fn try_encode_spring_security_hash_bytes(&self, unencoded_password: &[u8]) -> Result<String, PasswordEncoderError> {
+        // the encoded password is the password itself, so it has to be a string
+        String::from_utf8(unencoded_password.to_vec()).map_err(|_| PasswordEncoderError::InvalidUtf8Password)
     }
 }
 
@@ -17,6 +28,7 @@ impl PasswordEncoder for NoOpPasswordEncoder {
 mod tests {
     use super::PasswordEncoder;
     use crate::encoder::noop::NoOpPasswordEncoder;
+    use crate::error::PasswordEncoderError;
 
     #[test]
     fn encode_works() {
@@ -75,4 +87,12 @@ mod tests {
 
         assert!(result, "password should match stored one even if empty");
     }
+
+    #[test]
+    fn encode_fails_for_non_utf8_bytes() {
+        let encoder: NoOpPasswordEncoder = Default::default();
+
+        assert_eq!(encoder.try_encode_spring_security_hash_bytes(b"\xffHello"), Err(PasswordEncoderError::InvalidUtf8Password));
+        assert_eq!(encoder.try_encode_spring_security_hash_bytes(b"Hello"), Ok(String::from("Hello")));
+    }
 }
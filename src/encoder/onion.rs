@@ -0,0 +1,207 @@
+use crate::encoder::bcrypt::BCryptPasswordEncoder;
+pub use crate::encoder::md::LegacyDigestEncoder;
+use crate::encoder::md4::Md4PasswordEncoder;
+use crate::encoder::md5::Md5PasswordEncoder;
+use crate::error::PasswordEncoderError;
+use crate::PasswordEncoder;
+use zeroize::Zeroizing;
+
+/// Hashes the digests of a legacy encoder once more, like `bcrypt(md5(password))`, so stored legacy hashes
+/// can be strengthened without knowing the passwords
+///
+/// The salt of the legacy hash stays in front, `{salt}digest` gets stored as `{salt}$2a$10$...`.
+#[derive(Clone, Debug)]
+pub struct OnionPasswordEncoder<I, O> {
+    inner: I,
+    outer: O,
+}
+
+/// Registered as `bcrypt-md4` in the delegating password encoder
+pub type BCryptMd4PasswordEncoder = OnionPasswordEncoder<Md4PasswordEncoder, BCryptPasswordEncoder>;
+
+/// Registered as `bcrypt-md5` in the delegating password encoder
+///
+/// Other combinations are not registered, they are built with `OnionPasswordEncoder::new` and registered with
+/// `DelegatingPasswordEncoder::register_encoder`.
+pub type BCryptMd5PasswordEncoder = OnionPasswordEncoder<Md5PasswordEncoder, BCryptPasswordEncoder>;
+
+impl<I: Default, O: Default> Default for OnionPasswordEncoder<I, O> {
+    fn default() -> OnionPasswordEncoder<I, O> {
+        OnionPasswordEncoder {
+            inner: Default::default(),
+            outer: Default::default(),
+        }
+    }
+}
+
+impl<I: LegacyDigestEncoder, O: PasswordEncoder> OnionPasswordEncoder<I, O> {
+    /// Hashes the digests of the inner legacy encoder with the outer encoder
+    pub fn new(inner: I, outer: O) -> OnionPasswordEncoder<I, O> {
+        OnionPasswordEncoder { inner, outer }
+    }
+
+    /// Converts a hash of the inner encoder, this only needs the stored hash and not the password
+    pub fn wrap_legacy_hash(&self, legacy_encoded_password: &str) -> Result<String, PasswordEncoderError> {
+        let (salt, encoded_digest) = self.inner.split_salt(legacy_encoded_password);
+        let digest = self.inner.normalize_digest(encoded_digest)?;
+        Ok(salt.to_string() + &self.outer.try_encode_spring_security_hash_bytes(digest.as_bytes())?)
+    }
+
+    /// Converts a whole table of hashes offline, the results are in the order of the hashes
+    /// and a malformed hash does not stop the conversion of the others
+    pub fn wrap_legacy_hashes<L>(&self, legacy_encoded_passwords: L) -> Vec<Result<String, PasswordEncoderError>>
+    where
+        L: IntoIterator,
+        L::Item: AsRef<str>,
+    {
+        legacy_encoded_passwords
+            .into_iter()
+            .map(|legacy_encoded_password| self.wrap_legacy_hash(legacy_encoded_password.as_ref()))
+            .collect()
+    }
+}
+
+impl<I: LegacyDigestEncoder, O: PasswordEncoder> PasswordEncoder for OnionPasswordEncoder<I, O> {
+    fn matches_spring_security_hash(&self, unencoded_password: &str, encoded_password: &str) -> bool {
+        self.try_matches_spring_security_hash(unencoded_password, encoded_password).unwrap_or(false)
+    }
+
+    fn encode_spring_security_hash(&self, unencoded_password: &str) -> Option<String> {
+        self.try_encode_spring_security_hash(unencoded_password).ok()
+    }
+
+    fn try_matches_spring_security_hash_bytes(&self, unencoded_password: &[u8], encoded_password: &str) -> Result<bool, PasswordEncoderError> {
+        let (salt, outer_encoded_password) = self.inner.split_salt(encoded_password);
+        let digest = self.inner.encode_digest_with_salt(unencoded_password, salt);
+        self.outer.try_matches_spring_security_hash_bytes(digest.as_bytes(), outer_encoded_password)
+    }
+
+    fn try_encode_spring_security_hash_bytes(&self, unencoded_password: &[u8]) -> Result<String, PasswordEncoderError> {
+        let legacy_encoded_password = Zeroizing::new(self.inner.try_encode_spring_security_hash_bytes(unencoded_password)?);
+        self.wrap_legacy_hash(&legacy_encoded_password)
+    }
+
+    fn try_upgrade_encoding(&self, encoded_password: &str) -> Result<bool, PasswordEncoderError> {
+        let (_, outer_encoded_password) = self.inner.split_salt(encoded_password);
+        self.outer.try_upgrade_encoding(outer_encoded_password)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BCryptMd5PasswordEncoder, OnionPasswordEncoder};
+    use crate::encoder::argon2::Argon2PasswordEncoder;
+    use crate::encoder::bcrypt::{BCryptPasswordEncoder, BCryptVersion};
+    use crate::encoder::md5::Md5PasswordEncoder;
+    use crate::encoder::sha256::Sha256PasswordEncoder;
+    use crate::error::PasswordEncoderError;
+    use crate::PasswordEncoder;
+
+    fn bcrypt_md5_encoder() -> BCryptMd5PasswordEncoder {
+        OnionPasswordEncoder::new(Md5PasswordEncoder::default(), BCryptPasswordEncoder::new(BCryptVersion::TwoA, 4).unwrap())
+    }
+
+    #[test]
+    fn matches_wrapped_legacy_hash() {
+        let encoder = bcrypt_md5_encoder();
+
+        let encoded_password = encoder.wrap_legacy_hash("{thisissalt}2a4e7104c2780098f50ed5a84bb2323d").unwrap();
+
+        assert!(encoded_password.starts_with("{thisissalt}$2a$04$"));
+        assert!(encoder.matches_spring_security_hash("password", &encoded_password));
+        assert!(!encoder.matches_spring_security_hash("wrongpassword", &encoded_password));
+    }
+
+    #[test]
+    fn matches_wrapped_upper_case_hex() {
+        let encoder = bcrypt_md5_encoder();
+
+        let encoded_password = encoder.wrap_legacy_hash("{thisissalt}2A4E7104C2780098F50ED5A84BB2323D").unwrap();
+
+        assert!(encoder.matches_spring_security_hash("password", &encoded_password));
+    }
+
+    #[test]
+    fn matches_wrapped_unsalted_hash() {
+        let encoder = bcrypt_md5_encoder();
+
+        // md5 of "password"
+        let encoded_password = encoder.wrap_legacy_hash("5f4dcc3b5aa765d61d8327deb882cf99").unwrap();
+
+        assert!(encoded_password.starts_with("$2a$04$"));
+        assert!(encoder.matches_spring_security_hash("password", &encoded_password));
+    }
+
+    #[test]
+    fn wraps_base64_sha256_digests() {
+        let encoder = OnionPasswordEncoder::new(
+            Sha256PasswordEncoder::default().with_encode_hash_as_base64(true),
+            BCryptPasswordEncoder::new(BCryptVersion::TwoB, 4).unwrap(),
+        );
+
+        let encoded_password = encoder.wrap_legacy_hash("{THIS_IS_A_SALT}zmBACA39KR47iYKBxOdhx/b3fR6FvsFnQQKJcCVF/QY=").unwrap();
+
+        assert!(encoder.matches_spring_security_hash("boabspasswurd", &encoded_password));
+    }
+
+    #[test]
+    fn wraps_in_argon2() {
+        let encoder = OnionPasswordEncoder::new(Md5PasswordEncoder::default(), Argon2PasswordEncoder::new(16, 32, 1, 64, 1));
+
+        let encoded_password = encoder.wrap_legacy_hash("{thisissalt}2a4e7104c2780098f50ed5a84bb2323d").unwrap();
+
+        assert!(encoded_password.starts_with("{thisissalt}$argon2id$v=19$m=64,t=1,p=1$"));
+        assert!(encoder.matches_spring_security_hash("password", &encoded_password));
+        assert!(!encoder.matches_spring_security_hash("wrongpassword", &encoded_password));
+
+        let stronger_encoder = OnionPasswordEncoder::new(Md5PasswordEncoder::default(), Argon2PasswordEncoder::new(16, 32, 1, 128, 1));
+        assert!(stronger_encoder.upgrade_encoding(&encoded_password));
+    }
+
+    #[test]
+    fn error_for_malformed_legacy_hash() {
+        let encoder = bcrypt_md5_encoder();
+
+        assert_eq!(encoder.wrap_legacy_hash("{thisissalt}not hex"), Err(PasswordEncoderError::MalformedHash));
+        // already wrapped
+        assert_eq!(
+            encoder.wrap_legacy_hash("{thisissalt}$2a$04$aNGK8Lh0a8VMCS6bVgTaRuMdYW6HCbmZC.JqsmHOuUoSmP6EZq2H6"),
+            Err(PasswordEncoderError::MalformedHash)
+        );
+        // digest of another length
+        assert_eq!(encoder.wrap_legacy_hash("{thisissalt}2a4e7104"), Err(PasswordEncoderError::MalformedHash));
+    }
+
+    #[test]
+    fn wraps_batch_in_order() {
+        let encoder = bcrypt_md5_encoder();
+
+        let results = encoder.wrap_legacy_hashes(vec!["{thisissalt}2a4e7104c2780098f50ed5a84bb2323d", "not hex", "5f4dcc3b5aa765d61d8327deb882cf99"]);
+
+        assert_eq!(results.len(), 3);
+        assert!(encoder.matches_spring_security_hash("password", results[0].as_ref().unwrap()));
+        assert_eq!(results[1], Err(PasswordEncoderError::MalformedHash));
+        assert!(encoder.matches_spring_security_hash("password", results[2].as_ref().unwrap()));
+    }
+
+    #[test]
+    fn encodes_new_passwords() {
+        let encoder = bcrypt_md5_encoder();
+
+        let encoded_password = encoder.encode_spring_security_hash("password").unwrap();
+
+        assert!(encoded_password.starts_with('{'));
+        assert!(encoder.matches_spring_security_hash("password", &encoded_password));
+        assert!(!encoder.upgrade_encoding(&encoded_password));
+    }
+
+    #[test]
+    fn upgrades_like_outer_encoder() {
+        let encoder = bcrypt_md5_encoder();
+        let stronger_encoder = OnionPasswordEncoder::new(Md5PasswordEncoder::default(), BCryptPasswordEncoder::new(BCryptVersion::TwoA, 5).unwrap());
+
+        let encoded_password = encoder.wrap_legacy_hash("{thisissalt}2a4e7104c2780098f50ed5a84bb2323d").unwrap();
+
+        assert!(stronger_encoder.upgrade_encoding(&encoded_password));
+    }
+}
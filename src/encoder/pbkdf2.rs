@@ -1,3 +1,379 @@
+use crate::codec::BASE64;
+use crate::error::PasswordEncoderError;
+use crate::keygen::secure_random_bytes;
+use crate::password::SecretPassword;
+use crate::util::constant_time_equals;
+use crate::PasswordEncoder;
+use base64::Engine;
+use pbkdf2::pbkdf2_hmac;
+use sha1::Sha1;
+use sha2::{Sha256, Sha512};
+use zeroize::Zeroizing;
+
+// https://github.com/spring-projects/spring-security/blob/0e3ee8990299436223c3037afb096f454f17ef5e/crypto/src/main/java/org/springframework/security/crypto/password/Pbkdf2PasswordEncoder.java#L291
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SecretKeyFactoryAlgorithm {
+    PBKDF2WithHmacSHA1,
+    PBKDF2WithHmacSHA256,
+    PBKDF2WithHmacSHA512,
+}
+
+impl SecretKeyFactoryAlgorithm {
+    // hash width in bits, spring uses the digest length when configured by algorithm
+    fn hash_width(&self) -> usize {
+        match self {
+            SecretKeyFactoryAlgorithm::PBKDF2WithHmacSHA1 => 160,
+            SecretKeyFactoryAlgorithm::PBKDF2WithHmacSHA256 => 256,
+            SecretKeyFactoryAlgorithm::PBKDF2WithHmacSHA512 => 512,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
 pub struct Pbkdf2PasswordEncoder {
+    secret: SecretPassword,
+    salt_length: usize,
+    iterations: u32,
+    // in bits, like the key length of javax.crypto.spec.PBEKeySpec
+    hash_width: usize,
+    // the hash width follows the algorithm unless it was given to the constructor
+    override_hash_width: bool,
+    algorithm: SecretKeyFactoryAlgorithm,
     encode_hash_as_base64: bool,
 }
+
+impl Default for Pbkdf2PasswordEncoder {
+    fn default() -> Pbkdf2PasswordEncoder {
+        Pbkdf2PasswordEncoder::defaults_for_spring_security_v5_5()
+    }
+}
+
+impl Pbkdf2PasswordEncoder {
+    /// Registered as `pbkdf2` by the delegating encoder
+    pub fn defaults_for_spring_security_v5_5() -> Pbkdf2PasswordEncoder {
+        // https://github.com/spring-projects/spring-security/blob/0e3ee8990299436223c3037afb096f454f17ef5e/crypto/src/main/java/org/springframework/security/crypto/password/Pbkdf2PasswordEncoder.java#L93
+        Pbkdf2PasswordEncoder::new("", 8, 185000, 256)
+    }
+
+    /// Registered as `pbkdf2@SpringSecurity_v5_8` by the delegating encoder
+    pub fn defaults_for_spring_security_v5_8() -> Pbkdf2PasswordEncoder {
+        // https://github.com/spring-projects/spring-security/blob/0e3ee8990299436223c3037afb096f454f17ef5e/crypto/src/main/java/org/springframework/security/crypto/password/Pbkdf2PasswordEncoder.java
+        Pbkdf2PasswordEncoder::with_algorithm("", 16, 310000, SecretKeyFactoryAlgorithm::PBKDF2WithHmacSHA256)
+    }
+
+    /// Same parameter order as the Spring Security constructor, hash width is given in bits and PBKDF2WithHmacSHA1 is used
+    pub fn new(secret: &str, salt_length: usize, iterations: u32, hash_width: usize) -> Pbkdf2PasswordEncoder {
+        Pbkdf2PasswordEncoder {
+            secret: SecretPassword::from(secret),
+            salt_length,
+            iterations,
+            hash_width,
+            override_hash_width: false,
+            algorithm: SecretKeyFactoryAlgorithm::PBKDF2WithHmacSHA1,
+            encode_hash_as_base64: false,
+        }
+    }
+
+    /// Same parameter order as the Spring Security constructor, hash width is taken from the algorithm
+    pub fn with_algorithm(secret: &str, salt_length: usize, iterations: u32, algorithm: SecretKeyFactoryAlgorithm) -> Pbkdf2PasswordEncoder {
+        Pbkdf2PasswordEncoder {
+            secret: SecretPassword::from(secret),
+            salt_length,
+            iterations,
+            hash_width: algorithm.hash_width(),
+            override_hash_width: true,
+            algorithm,
+            encode_hash_as_base64: false,
+        }
+    }
+
+    /// Also sets the hash width to the one of the algorithm, unless the encoder was created with a hash width
+    // https://github.com/spring-projects/spring-security/blob/0e3ee8990299436223c3037afb096f454f17ef5e/crypto/src/main/java/org/springframework/security/crypto/password/Pbkdf2PasswordEncoder.java
+    pub fn set_algorithm(&mut self, algorithm: SecretKeyFactoryAlgorithm) {
+        self.algorithm = algorithm;
+        if self.override_hash_width {
+            self.hash_width = algorithm.hash_width();
+        }
+    }
+
+    pub fn set_encode_hash_as_base64(&mut self, encode_hash_as_base64: bool) {
+        self.encode_hash_as_base64 = encode_hash_as_base64;
+    }
+
+    pub(crate) fn salt_length(&self) -> usize {
+        self.salt_length
+    }
+
+    // in bits
+    pub(crate) fn hash_width(&self) -> usize {
+        self.hash_width
+    }
+
+    // returns the salt followed by the derived key
+    fn encode_with_salt(&self, unencoded_password: &[u8], salt: &[u8]) -> Result<Zeroizing<Vec<u8>>, PasswordEncoderError> {
+        // javax.crypto only accepts full bytes
+        let hash_length = self.hash_width / 8;
+        if hash_length == 0 || hash_length * 8 != self.hash_width {
+            return Err(PasswordEncoderError::InvalidParameters);
+        }
+
+        let mut salt_with_secret = Zeroizing::new(Vec::with_capacity(salt.len() + self.secret.as_bytes().len()));
+        salt_with_secret.extend_from_slice(salt);
+        salt_with_secret.extend_from_slice(self.secret.as_bytes());
+
+        let mut derived_key = Zeroizing::new(vec![0u8; hash_length]);
+        match self.algorithm {
+            SecretKeyFactoryAlgorithm::PBKDF2WithHmacSHA1 => pbkdf2_hmac::<Sha1>(unencoded_password, &salt_with_secret, self.iterations, &mut derived_key),
+            SecretKeyFactoryAlgorithm::PBKDF2WithHmacSHA256 => pbkdf2_hmac::<Sha256>(unencoded_password, &salt_with_secret, self.iterations, &mut derived_key),
+            SecretKeyFactoryAlgorithm::PBKDF2WithHmacSHA512 => pbkdf2_hmac::<Sha512>(unencoded_password, &salt_with_secret, self.iterations, &mut derived_key),
+        }
+
+        let mut result = Zeroizing::new(Vec::with_capacity(salt.len() + hash_length));
+        result.extend_from_slice(salt);
+        result.extend_from_slice(&derived_key);
+        Ok(result)
+    }
+
+    fn encode_bytes(&self, bytes: &[u8]) -> String {
+        if self.encode_hash_as_base64 {
+            return BASE64.encode(bytes);
+        }
+        hex::encode(bytes)
+    }
+
+    fn decode_bytes(&self, encoded_bytes: &str) -> Option<Vec<u8>> {
+        if self.encode_hash_as_base64 {
+            return BASE64.decode(encoded_bytes).ok();
+        }
+        hex::decode(encoded_bytes).ok()
+    }
+}
+
+impl PasswordEncoder for Pbkdf2PasswordEncoder {
+    fn matches_spring_security_hash(&self, unencoded_password: &str, encoded_password: &str) -> bool {
+        self.try_matches_spring_security_hash(unencoded_password, encoded_password).unwrap_or(false)
+    }
+
+    fn encode_spring_security_hash(&self, unencoded_password: &str) -> Option<String> {
+        self.try_encode_spring_security_hash(unencoded_password).ok()
+    }
+
+    fn try_matches_spring_security_hash_bytes(&self, unencoded_password: &[u8], encoded_password: &str) -> Result<bool, PasswordEncoderError> {
+        let digested = self.decode_bytes(encoded_password).ok_or(PasswordEncoderError::MalformedHash)?;
+        if digested.len() < self.salt_length {
+            return Err(PasswordEncoderError::MalformedHash);
+        }
+
+        let salt = &digested[..self.salt_length];
+        Ok(constant_time_equals(&digested, &self.encode_with_salt(unencoded_password, salt)?))
+    }
+
+    fn try_encode_spring_security_hash_bytes(&self, unencoded_password: &[u8]) -> Result<String, PasswordEncoderError> {
+        let salt = secure_random_bytes(self.salt_length)?;
+        let encoded = self.encode_with_salt(unencoded_password, &salt)?;
+        Ok(self.encode_bytes(&encoded))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PasswordEncoder, Pbkdf2PasswordEncoder, SecretKeyFactoryAlgorithm};
+    use crate::error::PasswordEncoderError;
+
+    #[test]
+    fn matches_default_hash() {
+        let encoder: Pbkdf2PasswordEncoder = Default::default();
+
+        let unencoded_password = String::from("password");
+        let encoded_password = String::from("0102030405060708a6e20384a37755b292cf50dfb3b04397b78941542c59b0dd6ca69be36b465087");
+
+        assert!(encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+    }
+
+    #[test]
+    fn matches_uppercased_hex() {
+        let encoder: Pbkdf2PasswordEncoder = Default::default();
+
+        let unencoded_password = String::from("password");
+        let encoded_password = String::from("0102030405060708A6E20384A37755B292CF50DFB3B04397B78941542C59B0DD6CA69BE36B465087");
+
+        assert!(encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+    }
+
+    #[test]
+    fn no_match_for_wrong_password() {
+        let encoder: Pbkdf2PasswordEncoder = Default::default();
+
+        let unencoded_password = String::from("wrongpassword");
+        let encoded_password = String::from("0102030405060708a6e20384a37755b292cf50dfb3b04397b78941542c59b0dd6ca69be36b465087");
+
+        assert!(!encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+    }
+
+    #[test]
+    fn matches_sha256_hash() {
+        let encoder = Pbkdf2PasswordEncoder::with_algorithm("", 16, 310000, SecretKeyFactoryAlgorithm::PBKDF2WithHmacSHA256);
+
+        let unencoded_password = String::from("password");
+        let encoded_password = String::from("000102030405060708090a0b0c0d0e0fe0f65a4bf6716253d2d10a7a4b18f35cd4baf31ff031a187cd0091674905482d");
+
+        assert!(encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+    }
+
+    #[test]
+    fn matches_sha512_hash_with_secret_as_base64() {
+        let mut encoder = Pbkdf2PasswordEncoder::with_algorithm("secret", 16, 1000, SecretKeyFactoryAlgorithm::PBKDF2WithHmacSHA512);
+        encoder.set_encode_hash_as_base64(true);
+
+        let unencoded_password = String::from("password");
+        let encoded_password = String::from("AAECAwQFBgcICQoLDA0OD+E0yWBvo5owWKvEm5K+T/GzxXoBUfVpKF5eadxls8ld/V05jAQ5vWS6siWHAGiZjKSIA+w52yrEroNrPxrKvOw=");
+
+        assert!(encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+    }
+
+    #[test]
+    fn set_algorithm_overrides_hash_width() {
+        let mut encoder = Pbkdf2PasswordEncoder::with_algorithm("secret", 16, 1000, SecretKeyFactoryAlgorithm::PBKDF2WithHmacSHA256);
+        encoder.set_algorithm(SecretKeyFactoryAlgorithm::PBKDF2WithHmacSHA512);
+        encoder.set_encode_hash_as_base64(true);
+
+        let unencoded_password = String::from("password");
+        let encoded_password = String::from("AAECAwQFBgcICQoLDA0OD+E0yWBvo5owWKvEm5K+T/GzxXoBUfVpKF5eadxls8ld/V05jAQ5vWS6siWHAGiZjKSIA+w52yrEroNrPxrKvOw=");
+
+        assert!(encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+    }
+
+    #[test]
+    fn set_algorithm_keeps_hash_width_given_to_constructor() {
+        let mut encoder = Pbkdf2PasswordEncoder::new("", 8, 1000, 160);
+        encoder.set_algorithm(SecretKeyFactoryAlgorithm::PBKDF2WithHmacSHA512);
+
+        let unencoded_password = String::from("password");
+        let encoded_password = encoder.encode_spring_security_hash(&unencoded_password).unwrap();
+
+        // 8 bytes salt and 20 bytes hash
+        assert_eq!(encoded_password.len(), 56);
+        assert!(encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+    }
+
+    #[test]
+    fn no_match_for_wrong_secret() {
+        let mut encoder = Pbkdf2PasswordEncoder::with_algorithm("othersecret", 16, 1000, SecretKeyFactoryAlgorithm::PBKDF2WithHmacSHA512);
+        encoder.set_encode_hash_as_base64(true);
+
+        let unencoded_password = String::from("password");
+        let encoded_password = String::from("AAECAwQFBgcICQoLDA0OD+E0yWBvo5owWKvEm5K+T/GzxXoBUfVpKF5eadxls8ld/V05jAQ5vWS6siWHAGiZjKSIA+w52yrEroNrPxrKvOw=");
+
+        assert!(!encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+    }
+
+    #[test]
+    fn matches_legacy_hash_width() {
+        // spring security 4 used 360000 iterations and a hash width of 160 bits
+        let encoder = Pbkdf2PasswordEncoder::new("", 8, 360000, 160);
+
+        let unencoded_password = String::from("password");
+        let encoded_password = String::from("010203040506070839e6bbeadf98b4f9024a1184f61d4001aebba622");
+
+        assert!(encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+    }
+
+    #[test]
+    fn no_match_for_too_short_hash() {
+        let encoder: Pbkdf2PasswordEncoder = Default::default();
+
+        let unencoded_password = String::from("password");
+        let encoded_password = String::from("010203");
+
+        assert!(!encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+    }
+
+    #[test]
+    fn no_match_for_malformed_hex() {
+        let encoder: Pbkdf2PasswordEncoder = Default::default();
+
+        let unencoded_password = String::from("password");
+        let encoded_password = String::from("not hex at all");
+
+        assert!(!encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+    }
+
+    #[test]
+    fn encode_with_defaults() {
+        let encoder: Pbkdf2PasswordEncoder = Default::default();
+
+        let unencoded_password = String::from("password");
+        let encoded_password = encoder.encode_spring_security_hash(&unencoded_password).unwrap();
+
+        // 8 bytes salt and 32 bytes hash
+        assert_eq!(encoded_password.len(), 80);
+        assert!(encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+    }
+
+    #[test]
+    fn encode_as_base64() {
+        let mut encoder = Pbkdf2PasswordEncoder::with_algorithm("secret", 16, 1000, SecretKeyFactoryAlgorithm::PBKDF2WithHmacSHA256);
+        encoder.set_encode_hash_as_base64(true);
+
+        let unencoded_password = String::from("password");
+        let encoded_password = encoder.encode_spring_security_hash(&unencoded_password).unwrap();
+
+        // 16 bytes salt and 32 bytes hash
+        assert_eq!(encoded_password.len(), 64);
+        assert!(encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+    }
+
+    #[test]
+    fn debug_redacts_secret() {
+        let encoder = Pbkdf2PasswordEncoder::new("topsecret", 16, 1000, 256);
+
+        let debug = format!("{:?}", encoder);
+
+        assert!(debug.contains("secret: SecretPassword(***)"));
+        assert!(!debug.contains("topsecret"));
+    }
+
+    #[test]
+    fn encode_fails_on_partial_byte_hash_width() {
+        let encoder = Pbkdf2PasswordEncoder::new("", 8, 1000, 100);
+
+        assert!(encoder.encode_spring_security_hash(&String::from("password")).is_none());
+        assert_eq!(encoder.try_encode_spring_security_hash("password"), Err(PasswordEncoderError::InvalidParameters));
+    }
+
+    #[test]
+    fn error_for_malformed_hash() {
+        let encoder: Pbkdf2PasswordEncoder = Default::default();
+
+        assert_eq!(
+            encoder.try_matches_spring_security_hash("password", "not hex at all"),
+            Err(PasswordEncoderError::MalformedHash)
+        );
+        assert_eq!(encoder.try_matches_spring_security_hash("password", "010203"), Err(PasswordEncoderError::MalformedHash));
+        assert_eq!(
+            encoder.try_matches_spring_security_hash("wrongpassword", "0102030405060708a6e20384a37755b292cf50dfb3b04397b78941542c59b0dd6ca69be36b465087"),
+            Ok(false)
+        );
+    }
+
+    #[test]
+    fn matches_spring_security_v5_8_hash() {
+        let encoder = Pbkdf2PasswordEncoder::defaults_for_spring_security_v5_8();
+
+        let encoded_password = "0102030405060708090a0b0c0d0e0f10b94f951e97b99c4f592819f1d14004b125bf97431436c8043bc8faa95781b74a";
+
+        assert!(encoder.matches_spring_security_hash("password", encoded_password));
+        assert!(!Pbkdf2PasswordEncoder::defaults_for_spring_security_v5_5().matches_spring_security_hash("password", encoded_password));
+    }
+
+    #[test]
+    fn encode_spring_security_v5_8() {
+        let encoder = Pbkdf2PasswordEncoder::defaults_for_spring_security_v5_8();
+
+        let encoded_password = encoder.encode_spring_security_hash("password").unwrap();
+
+        // 16 bytes salt and 32 bytes hash
+        assert_eq!(encoded_password.len(), 96);
+        assert!(encoder.matches_spring_security_hash("password", &encoded_password));
+    }
+}
@@ -0,0 +1,405 @@
+use crate::codec::BASE64;
+use crate::error::PasswordEncoderError;
+use crate::password::SecretPassword;
+use crate::PasswordEncoder;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::sync::{PoisonError, RwLock};
+use zeroize::{Zeroize, Zeroizing};
+
+// "$pepper$" + key id + "$" + hash of the wrapped encoder
+const PEPPER_PREFIX: &str = "$pepper$";
+const KEY_ID_SUFFIX: char = '$';
+
+type KeyProvider = Box<dyn Fn(&str) -> Option<SecretPassword> + Send + Sync>;
+
+/// Loads a pepper key from an environment variable, the value is used as key as is and does not need to be UTF-8 on Unix
+pub fn key_from_env(variable: &str) -> Result<SecretPassword, PasswordEncoderError> {
+    match std::env::var_os(variable).and_then(os_string_into_bytes) {
+        Some(key) if !key.is_empty() => Ok(SecretPassword::new(key)),
+        _ => Err(PasswordEncoderError::KeyUnavailable(variable.to_string())),
+    }
+}
+
+#[cfg(unix)]
+fn os_string_into_bytes(value: OsString) -> Option<Vec<u8>> {
+    use std::os::unix::ffi::OsStringExt;
+    Some(value.into_vec())
+}
+
+// other platforms have no lossless byte representation
+#[cfg(not(unix))]
+fn os_string_into_bytes(value: OsString) -> Option<Vec<u8>> {
+    value.into_string().ok().map(String::into_bytes)
+}
+
+/// Loads a pepper key from a file, trailing line breaks are not part of the key
+pub fn key_from_file(path: impl AsRef<Path>) -> Result<SecretPassword, PasswordEncoderError> {
+    let path = path.as_ref();
+    let mut key = fs::read(path).map_err(|_| PasswordEncoderError::KeyUnavailable(path.display().to_string()))?;
+    while matches!(key.last(), Some(b'\n' | b'\r')) {
+        key.pop();
+    }
+    if key.is_empty() {
+        return Err(PasswordEncoderError::KeyUnavailable(path.display().to_string()));
+    }
+    Ok(SecretPassword::new(key))
+}
+
+/// Hashes an HMAC-SHA256 of the password instead of the password itself, keyed by an application-wide pepper
+/// which is held outside the database
+///
+/// The id of the key is stored in front of the hash, like `$pepper$2024$` followed by the hash of the wrapped encoder,
+/// so hashes peppered with older keys keep matching and get upgraded on the next login.
+pub struct PepperedPasswordEncoder<E> {
+    encoder: E,
+    key_id_for_encode: String,
+    // keys returned by the key provider get added, matching only needs a shared reference
+    keys: RwLock<HashMap<String, SecretPassword>>,
+    // asked for ids not in keys, like keys held by a key management service
+    key_provider: Option<KeyProvider>,
+    match_unpeppered: bool,
+}
+
+impl<E: fmt::Debug> fmt::Debug for PepperedPasswordEncoder<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let keys = self.keys.read().unwrap_or_else(PoisonError::into_inner);
+        let mut key_ids: Vec<&String> = keys.keys().collect();
+        key_ids.sort();
+        f.debug_struct("PepperedPasswordEncoder")
+            .field("encoder", &self.encoder)
+            .field("key_id_for_encode", &self.key_id_for_encode)
+            .field("key_ids", &key_ids)
+            .field("key_provider", &self.key_provider.is_some())
+            .field("match_unpeppered", &self.match_unpeppered)
+            .finish()
+    }
+}
+
+fn is_valid_key_id(key_id: &str) -> bool {
+    !key_id.is_empty() && !key_id.contains(KEY_ID_SUFFIX)
+}
+
+fn split_key_id(encoded_password: &str) -> Option<(&str, &str)> {
+    let encoded_password_without_prefix = encoded_password.strip_prefix(PEPPER_PREFIX)?;
+    let position = encoded_password_without_prefix.find(KEY_ID_SUFFIX)?;
+    let key_id = &encoded_password_without_prefix[..position];
+    if key_id.is_empty() {
+        return None;
+    }
+    Some((key_id, &encoded_password_without_prefix[position + 1..]))
+}
+
+fn pepper(key: &SecretPassword, unencoded_password: &[u8]) -> Result<Zeroizing<String>, PasswordEncoderError> {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(key.as_bytes()).map_err(|_| PasswordEncoderError::InvalidParameters)?;
+    mac.update(unencoded_password);
+    let mut digest = mac.finalize().into_bytes();
+    // base64, so encoders only accepting UTF-8 passwords can hash it too
+    let peppered_password = Zeroizing::new(BASE64.encode(digest));
+    digest.as_mut_slice().zeroize();
+    Ok(peppered_password)
+}
+
+impl<E: PasswordEncoder> PepperedPasswordEncoder<E> {
+    /// Peppers with the given key for encoding, nothing is returned for an empty key id or one containing `$`
+    pub fn new(encoder: E, key_id_for_encode: &str, key: SecretPassword) -> Option<PepperedPasswordEncoder<E>> {
+        if !is_valid_key_id(key_id_for_encode) {
+            return None;
+        }
+        let mut keys = HashMap::new();
+        keys.insert(key_id_for_encode.to_string(), key);
+        Some(PepperedPasswordEncoder {
+            encoder,
+            key_id_for_encode: key_id_for_encode.to_string(),
+            keys: RwLock::new(keys),
+            key_provider: None,
+            match_unpeppered: false,
+        })
+    }
+
+    /// Adds a key only used for matching, like the previous key after a rotation, returns false for an invalid key id
+    pub fn add_key(&mut self, key_id: &str, key: SecretPassword) -> bool {
+        if !is_valid_key_id(key_id) {
+            return false;
+        }
+        self.keys.get_mut().unwrap_or_else(PoisonError::into_inner).insert(key_id.to_string(), key);
+        true
+    }
+
+    /// Selects the key used to encode, returns false and keeps the current one when no key is added for the id
+    pub fn set_key_id_for_encode(&mut self, key_id_for_encode: &str) -> bool {
+        if !self.keys.get_mut().unwrap_or_else(PoisonError::into_inner).contains_key(key_id_for_encode) {
+            return false;
+        }
+        self.key_id_for_encode = key_id_for_encode.to_string();
+        true
+    }
+
+    /// Asks the callback for keys of ids that were not added, it returns nothing for unknown ids
+    ///
+    /// Returned keys are kept like added ones, so the callback is asked only once per key id and not on every login.
+    pub fn set_key_provider<F: Fn(&str) -> Option<SecretPassword> + Send + Sync + 'static>(&mut self, key_provider: F) {
+        self.key_provider = Some(Box::new(key_provider));
+    }
+
+    /// Matches hashes stored before peppering was introduced with the wrapped encoder, so they get peppered on the next
+    /// login, off by default as hashes without pepper are rejected then
+    pub fn set_match_unpeppered(&mut self, match_unpeppered: bool) {
+        self.match_unpeppered = match_unpeppered;
+    }
+
+    pub fn with_match_unpeppered(mut self, match_unpeppered: bool) -> Self {
+        self.set_match_unpeppered(match_unpeppered);
+        self
+    }
+
+    pub fn with_key(mut self, key_id: &str, key: SecretPassword) -> Self {
+        self.add_key(key_id, key);
+        self
+    }
+
+    pub fn with_key_provider<F: Fn(&str) -> Option<SecretPassword> + Send + Sync + 'static>(mut self, key_provider: F) -> Self {
+        self.set_key_provider(key_provider);
+        self
+    }
+
+    fn peppered_password(&self, key_id: &str, unencoded_password: &[u8]) -> Result<Zeroizing<String>, PasswordEncoderError> {
+        if let Some(key) = self.keys.read().unwrap_or_else(PoisonError::into_inner).get(key_id) {
+            return pepper(key, unencoded_password);
+        }
+        // the lock is not held while asking, the callback might take a while
+        let key = self
+            .key_provider
+            .as_ref()
+            .and_then(|key_provider| key_provider(key_id))
+            .ok_or_else(|| PasswordEncoderError::UnmappedKeyId(key_id.to_string()))?;
+        let peppered_password = pepper(&key, unencoded_password)?;
+        self.keys.write().unwrap_or_else(PoisonError::into_inner).insert(key_id.to_string(), key);
+        Ok(peppered_password)
+    }
+}
+
+impl<E: PasswordEncoder> PasswordEncoder for PepperedPasswordEncoder<E> {
+    fn matches_spring_security_hash(&self, unencoded_password: &str, encoded_password: &str) -> bool {
+        self.try_matches_spring_security_hash(unencoded_password, encoded_password).unwrap_or(false)
+    }
+
+    fn encode_spring_security_hash(&self, unencoded_password: &str) -> Option<String> {
+        self.try_encode_spring_security_hash(unencoded_password).ok()
+    }
+
+    fn try_matches_spring_security_hash_bytes(&self, unencoded_password: &[u8], encoded_password: &str) -> Result<bool, PasswordEncoderError> {
+        let (key_id, inner_encoded_password) = match split_key_id(encoded_password) {
+            Some(key_id_and_inner_encoded_password) => key_id_and_inner_encoded_password,
+            None if self.match_unpeppered => return self.encoder.try_matches_spring_security_hash_bytes(unencoded_password, encoded_password),
+            None => return Err(PasswordEncoderError::MalformedHash),
+        };
+        let peppered_password = self.peppered_password(key_id, unencoded_password)?;
+        self.encoder.try_matches_spring_security_hash_bytes(peppered_password.as_bytes(), inner_encoded_password)
+    }
+
+    fn try_encode_spring_security_hash_bytes(&self, unencoded_password: &[u8]) -> Result<String, PasswordEncoderError> {
+        let peppered_password = self.peppered_password(&self.key_id_for_encode, unencoded_password)?;
+        let inner_encoded_password = self.encoder.try_encode_spring_security_hash_bytes(peppered_password.as_bytes())?;
+        Ok(format!("{}{}{}{}", PEPPER_PREFIX, self.key_id_for_encode, KEY_ID_SUFFIX, inner_encoded_password))
+    }
+
+    // hashes peppered with another key get encoded again, like the delegating encoder does for other ids,
+    // hashes without pepper only when they are matched at all
+    fn try_upgrade_encoding(&self, encoded_password: &str) -> Result<bool, PasswordEncoderError> {
+        match split_key_id(encoded_password) {
+            Some((key_id, inner_encoded_password)) if key_id == self.key_id_for_encode => self.encoder.try_upgrade_encoding(inner_encoded_password),
+            Some(_) => Ok(true),
+            None if self.match_unpeppered => Ok(true),
+            None => Err(PasswordEncoderError::MalformedHash),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{key_from_env, key_from_file, PepperedPasswordEncoder};
+    use crate::encoder::bcrypt::{BCryptPasswordEncoder, BCryptVersion};
+    use crate::encoder::noop::NoOpPasswordEncoder;
+    use crate::error::PasswordEncoderError;
+    use crate::password::SecretPassword;
+    use crate::verification::Verification;
+    use crate::PasswordEncoder;
+    use std::fs;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    fn noop_encoder(key_id: &str, key: &str) -> PepperedPasswordEncoder<NoOpPasswordEncoder> {
+        PepperedPasswordEncoder::new(NoOpPasswordEncoder, key_id, SecretPassword::from(key)).unwrap()
+    }
+
+    #[test]
+    fn is_send_and_sync() {
+        fn assert_send_and_sync<T: Send + Sync>() {}
+        assert_send_and_sync::<PepperedPasswordEncoder<BCryptPasswordEncoder>>();
+    }
+
+    #[test]
+    fn encodes_hmac_of_password_with_key_id() {
+        let encoder = noop_encoder("2024", "key");
+
+        // HMAC-SHA256 of "password" keyed with "key" as Base64
+        assert_eq!(
+            encoder.try_encode_spring_security_hash("password"),
+            Ok(String::from("$pepper$2024$TUL7n/yNfQokVClDi0vHPbEAehZwJqCgxqdPpY6Ohso="))
+        );
+    }
+
+    #[test]
+    fn matches_peppered_hash() {
+        let encoder = PepperedPasswordEncoder::new(BCryptPasswordEncoder::new(BCryptVersion::TwoB, 4).unwrap(), "2024", SecretPassword::from("key")).unwrap();
+
+        let encoded_password = encoder.encode_spring_security_hash("password").unwrap();
+
+        assert!(encoded_password.starts_with("$pepper$2024$$2b$04$"));
+        assert!(encoder.matches_spring_security_hash("password", &encoded_password));
+        assert!(!encoder.matches_spring_security_hash("wrongpassword", &encoded_password));
+    }
+
+    #[test]
+    fn no_match_for_other_key() {
+        let encoder = noop_encoder("2024", "other key");
+
+        assert_eq!(
+            encoder.try_matches_spring_security_hash("password", "$pepper$2024$TUL7n/yNfQokVClDi0vHPbEAehZwJqCgxqdPpY6Ohso="),
+            Ok(false)
+        );
+    }
+
+    #[test]
+    fn matches_with_rotated_key() {
+        let encoder = noop_encoder("2025", "new key").with_key("2024", SecretPassword::from("key"));
+
+        let stored_password = "$pepper$2024$TUL7n/yNfQokVClDi0vHPbEAehZwJqCgxqdPpY6Ohso=";
+
+        assert!(encoder.matches_spring_security_hash("password", stored_password));
+        assert!(encoder.upgrade_encoding(stored_password));
+        match encoder.try_verify_and_upgrade_spring_security_hash("password", stored_password) {
+            Ok(Verification::Upgrade(upgraded_password)) => {
+                assert!(upgraded_password.starts_with("$pepper$2025$"));
+                assert!(!encoder.upgrade_encoding(&upgraded_password));
+            }
+            result => panic!("expected upgrade, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn matches_with_key_from_provider() {
+        let encoder = noop_encoder("2025", "new key").with_key_provider(|key_id| match key_id {
+            "2024" => Some(SecretPassword::from("key")),
+            _ => None,
+        });
+
+        assert!(encoder.matches_spring_security_hash("password", "$pepper$2024$TUL7n/yNfQokVClDi0vHPbEAehZwJqCgxqdPpY6Ohso="));
+        assert_eq!(
+            encoder.try_matches_spring_security_hash("password", "$pepper$2023$TUL7n/yNfQokVClDi0vHPbEAehZwJqCgxqdPpY6Ohso="),
+            Err(PasswordEncoderError::UnmappedKeyId(String::from("2023")))
+        );
+    }
+
+    #[test]
+    fn asks_key_provider_once_per_key_id() {
+        let asked = Arc::new(AtomicUsize::new(0));
+        let asked_by_provider = Arc::clone(&asked);
+        let encoder = noop_encoder("2025", "new key").with_key_provider(move |_| {
+            asked_by_provider.fetch_add(1, Ordering::SeqCst);
+            Some(SecretPassword::from("key"))
+        });
+
+        for _ in 0..3 {
+            assert!(encoder.matches_spring_security_hash("password", "$pepper$2024$TUL7n/yNfQokVClDi0vHPbEAehZwJqCgxqdPpY6Ohso="));
+        }
+
+        assert_eq!(asked.load(Ordering::SeqCst), 1);
+        assert!(format!("{:?}", encoder).contains("\"2024\""));
+    }
+
+    #[test]
+    fn error_for_hash_without_pepper() {
+        let encoder = noop_encoder("2024", "key");
+
+        assert_eq!(encoder.try_matches_spring_security_hash("password", "password"), Err(PasswordEncoderError::MalformedHash));
+        assert_eq!(
+            encoder.try_matches_spring_security_hash("password", "$pepper$$password"),
+            Err(PasswordEncoderError::MalformedHash)
+        );
+        assert_eq!(encoder.try_upgrade_encoding("password"), Err(PasswordEncoderError::MalformedHash));
+    }
+
+    #[test]
+    fn peppers_unpeppered_hash_on_login() {
+        let encoder = noop_encoder("2024", "key").with_match_unpeppered(true);
+
+        assert!(!encoder.matches_spring_security_hash("wrongpassword", "password"));
+        match encoder.try_verify_and_upgrade_spring_security_hash("password", "password") {
+            Ok(Verification::Upgrade(upgraded_password)) => {
+                assert_eq!(upgraded_password, "$pepper$2024$TUL7n/yNfQokVClDi0vHPbEAehZwJqCgxqdPpY6Ohso=");
+                assert!(!encoder.upgrade_encoding(&upgraded_password));
+            }
+            result => panic!("expected upgrade, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_key_ids() {
+        assert!(PepperedPasswordEncoder::new(NoOpPasswordEncoder, "", SecretPassword::from("key")).is_none());
+        assert!(PepperedPasswordEncoder::new(NoOpPasswordEncoder, "20$24", SecretPassword::from("key")).is_none());
+
+        let mut encoder = noop_encoder("2024", "key");
+        assert!(!encoder.add_key("20$25", SecretPassword::from("key")));
+        assert!(!encoder.set_key_id_for_encode("2025"));
+        assert!(encoder.add_key("2025", SecretPassword::from("new key")));
+        assert!(encoder.set_key_id_for_encode("2025"));
+    }
+
+    #[test]
+    fn loads_key_from_env() {
+        std::env::set_var("PEPPER_TESTS_KEY", "key");
+
+        assert_eq!(key_from_env("PEPPER_TESTS_KEY").unwrap().as_bytes(), b"key");
+        #[cfg(unix)]
+        {
+            use std::ffi::OsStr;
+            use std::os::unix::ffi::OsStrExt;
+            std::env::set_var("PEPPER_TESTS_BINARY_KEY", OsStr::from_bytes(b"\xffkey"));
+            assert_eq!(key_from_env("PEPPER_TESTS_BINARY_KEY").unwrap().as_bytes(), b"\xffkey");
+        }
+        assert_eq!(
+            key_from_env("PEPPER_TESTS_MISSING_KEY"),
+            Err(PasswordEncoderError::KeyUnavailable(String::from("PEPPER_TESTS_MISSING_KEY")))
+        );
+    }
+
+    #[test]
+    fn loads_key_from_file_without_line_break() {
+        let path = std::env::temp_dir().join(format!("pepper-tests-{}.key", std::process::id()));
+        fs::write(&path, "key\r\n").unwrap();
+
+        let key = key_from_file(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(key.unwrap().as_bytes(), b"key");
+        assert_eq!(key_from_file(&path), Err(PasswordEncoderError::KeyUnavailable(path.display().to_string())));
+    }
+
+    #[test]
+    fn debug_output_hides_keys() {
+        let encoder = noop_encoder("2024", "very secret key");
+
+        let output = format!("{:?}", encoder);
+
+        assert!(output.contains("2024"));
+        assert!(!output.contains("very secret key"));
+    }
+}
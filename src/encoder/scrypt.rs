@@ -1,3 +1,23 @@
+use crate::codec::BASE64;
+use crate::error::PasswordEncoderError;
+use crate::keygen::secure_random_bytes;
+use crate::util::constant_time_equals;
+use crate::PasswordEncoder;
+use base64::Engine;
+use scrypt::{scrypt, Params};
+use zeroize::Zeroizing;
+
+// memory cost and parallelization get stored in one byte each
+const MAX_PACKED_COST: u32 = 0xff;
+// hashing allocates 128 * r * N bytes, so stored parameters are limited to 16 times the v5.8 defaults
+// instead of aborting on hostile hashes
+const MAX_MEMORY: u64 = 1 << 30;
+
+fn required_memory(log_cpu_cost: u32, memory_cost: u32) -> u64 {
+    (128 * memory_cost as u64) << log_cpu_cost
+}
+
+#[derive(Clone, Copy, Debug)]
 pub struct SCryptPasswordEncoder {
     cpu_cost: i32,
     memory_cost: i32,
@@ -5,3 +25,377 @@ pub struct SCryptPasswordEncoder {
     key_length: i32,
     salt_length: i32,
 }
+
+impl Default for SCryptPasswordEncoder {
+    fn default() -> SCryptPasswordEncoder {
+        SCryptPasswordEncoder::defaults_for_spring_security_v4_1()
+    }
+}
+
+impl SCryptPasswordEncoder {
+    /// Registered as `scrypt` by the delegating encoder
+    pub fn defaults_for_spring_security_v4_1() -> SCryptPasswordEncoder {
+        // https://github.com/spring-projects/spring-security/blob/0e3ee8990299436223c3037afb096f454f17ef5e/crypto/src/main/java/org/springframework/security/crypto/scrypt/SCryptPasswordEncoder.java#L70-L78
+        SCryptPasswordEncoder {
+            cpu_cost: 16384,
+            memory_cost: 8,
+            parallelization: 1,
+            key_length: 32,
+            salt_length: 64,
+        }
+    }
+
+    /// Registered as `scrypt@SpringSecurity_v5_8` by the delegating encoder
+    pub fn defaults_for_spring_security_v5_8() -> SCryptPasswordEncoder {
+        // https://github.com/spring-projects/spring-security/blob/0e3ee8990299436223c3037afb096f454f17ef5e/crypto/src/main/java/org/springframework/security/crypto/scrypt/SCryptPasswordEncoder.java
+        SCryptPasswordEncoder {
+            cpu_cost: 65536,
+            memory_cost: 8,
+            parallelization: 1,
+            key_length: 32,
+            salt_length: 16,
+        }
+    }
+
+    /// Same parameter order as the Spring Security constructor, returns nothing when Spring Security would throw,
+    /// for memory cost or parallelization above 255 which can not be stored and when hashing would need more than 1 GiB
+    pub fn new(cpu_cost: i32, memory_cost: i32, parallelization: i32, key_length: i32, salt_length: i32) -> Option<SCryptPasswordEncoder> {
+        // https://github.com/spring-projects/spring-security/blob/0e3ee8990299436223c3037afb096f454f17ef5e/crypto/src/main/java/org/springframework/security/crypto/scrypt/SCryptPasswordEncoder.java#L113-L133
+        if cpu_cost <= 1 || (memory_cost == 1 && cpu_cost > 65536) || memory_cost < 1 {
+            return None;
+        }
+        let max_parallel = i32::MAX as i64 / (128 * memory_cost as i64 * 8);
+        if parallelization < 1 || parallelization as i64 > max_parallel || key_length < 1 || salt_length < 1 {
+            return None;
+        }
+        // the parameters must be stored as log2 of the cpu cost
+        if !(cpu_cost as u32).is_power_of_two() {
+            return None;
+        }
+        if memory_cost as u32 > MAX_PACKED_COST || parallelization as u32 > MAX_PACKED_COST || required_memory(cpu_cost.trailing_zeros(), memory_cost as u32) > MAX_MEMORY {
+            return None;
+        }
+
+        Some(SCryptPasswordEncoder {
+            cpu_cost,
+            memory_cost,
+            parallelization,
+            key_length,
+            salt_length,
+        })
+    }
+}
+
+// "$" + hex(log2(N) << 16 | r << 8 | p) + "$" + base64(salt) + "$" + base64(derived key)
+#[derive(Debug, PartialEq)]
+pub(crate) struct SCryptHash {
+    pub(crate) log_cpu_cost: u8,
+    pub(crate) memory_cost: u32,
+    pub(crate) parallelization: u32,
+    pub(crate) salt: Vec<u8>,
+    pub(crate) derived_key: Vec<u8>,
+}
+
+impl SCryptHash {
+    pub(crate) fn decode(encoded_password: &str) -> Option<SCryptHash> {
+        let parts: Vec<&str> = encoded_password.split('$').collect();
+        if parts.len() != 4 || !parts[0].is_empty() {
+            return None;
+        }
+
+        let params = i64::from_str_radix(parts[1], 16).ok()?;
+        let log_cpu_cost = params >> 16 & 0xffff;
+        let memory_cost = (params >> 8 & 0xff) as u32;
+        let parallelization = (params & 0xff) as u32;
+
+        // same limits as bouncy castle applies while generating, java can not handle a cpu cost of 2^31
+        if !(1..=30).contains(&log_cpu_cost) || memory_cost < 1 || (memory_cost == 1 && log_cpu_cost >= 16) {
+            return None;
+        }
+        let max_parallel = i32::MAX as u32 / (128 * memory_cost * 8);
+        if parallelization < 1 || parallelization > max_parallel || required_memory(log_cpu_cost as u32, memory_cost) > MAX_MEMORY {
+            return None;
+        }
+
+        Some(SCryptHash {
+            log_cpu_cost: log_cpu_cost as u8,
+            memory_cost,
+            parallelization,
+            salt: BASE64.decode(parts[2]).ok()?,
+            derived_key: BASE64.decode(parts[3]).ok()?,
+        })
+    }
+
+    pub(crate) fn encode(&self) -> String {
+        let params = (self.log_cpu_cost as u64) << 16 | (self.memory_cost as u64) << 8 | self.parallelization as u64;
+        format!("${:x}${}${}", params, BASE64.encode(&self.salt), BASE64.encode(&self.derived_key))
+    }
+
+    fn generate(&self, unencoded_password: &[u8], key_length: usize) -> Result<Zeroizing<Vec<u8>>, PasswordEncoderError> {
+        // the length inside the params is not used for the raw derivation
+        let params = Params::new(self.log_cpu_cost, self.memory_cost, self.parallelization, Params::RECOMMENDED_LEN).map_err(|_| PasswordEncoderError::InvalidParameters)?;
+        let mut derived_key = Zeroizing::new(vec![0u8; key_length]);
+        scrypt(unencoded_password, &self.salt, &params, &mut derived_key).map_err(|_| PasswordEncoderError::InvalidParameters)?;
+        Ok(derived_key)
+    }
+}
+
+impl PasswordEncoder for SCryptPasswordEncoder {
+    fn matches_spring_security_hash(&self, unencoded_password: &str, encoded_password: &str) -> bool {
+        self.try_matches_spring_security_hash(unencoded_password, encoded_password).unwrap_or(false)
+    }
+
+    fn encode_spring_security_hash(&self, unencoded_password: &str) -> Option<String> {
+        self.try_encode_spring_security_hash(unencoded_password).ok()
+    }
+
+    fn try_matches_spring_security_hash_bytes(&self, unencoded_password: &[u8], encoded_password: &str) -> Result<bool, PasswordEncoderError> {
+        if encoded_password.len() < self.key_length as usize {
+            return Err(PasswordEncoderError::MalformedHash);
+        }
+        let decoded = SCryptHash::decode(encoded_password).ok_or(PasswordEncoderError::MalformedHash)?;
+
+        // spring security uses the configured key length, not the stored one
+        let generated = decoded.generate(unencoded_password, self.key_length as usize)?;
+        Ok(constant_time_equals(&decoded.derived_key, &generated))
+    }
+
+    fn try_encode_spring_security_hash_bytes(&self, unencoded_password: &[u8]) -> Result<String, PasswordEncoderError> {
+        // would overflow into the neighbouring parameters and never match again
+        if self.memory_cost as u32 > MAX_PACKED_COST || self.parallelization as u32 > MAX_PACKED_COST {
+            return Err(PasswordEncoderError::InvalidParameters);
+        }
+        let mut encoded = SCryptHash {
+            log_cpu_cost: self.cpu_cost.trailing_zeros() as u8,
+            memory_cost: self.memory_cost as u32,
+            parallelization: self.parallelization as u32,
+            salt: secure_random_bytes(self.salt_length as usize)?,
+            derived_key: vec![],
+        };
+        encoded.derived_key = encoded.generate(unencoded_password, self.key_length as usize)?.to_vec();
+
+        Ok(encoded.encode())
+    }
+
+    /// Upgrades hashes with any cost parameter lower than configured
+    fn try_upgrade_encoding(&self, encoded_password: &str) -> Result<bool, PasswordEncoderError> {
+        // https://github.com/spring-projects/spring-security/blob/0e3ee8990299436223c3037afb096f454f17ef5e/crypto/src/main/java/org/springframework/security/crypto/scrypt/SCryptPasswordEncoder.java
+        if encoded_password.is_empty() {
+            return Ok(false);
+        }
+        let decoded = SCryptHash::decode(encoded_password).ok_or(PasswordEncoderError::MalformedHash)?;
+        Ok((1i64 << decoded.log_cpu_cost) < self.cpu_cost as i64
+            || (decoded.memory_cost as i64) < self.memory_cost as i64
+            || (decoded.parallelization as i64) < self.parallelization as i64)
+    }
+}
+
+#[cfg(test)]
+mod decoding_tests {
+    use super::SCryptHash;
+
+    #[test]
+    fn decodes_params_salt_and_key() {
+        let decoded = SCryptHash::decode("$e0801$c29tZXNhbHQ=$cGFzc3dvcmQ=").unwrap();
+
+        assert_eq!(decoded.log_cpu_cost, 14);
+        assert_eq!(decoded.memory_cost, 8);
+        assert_eq!(decoded.parallelization, 1);
+        assert_eq!(decoded.salt, b"somesalt");
+        assert_eq!(decoded.derived_key, b"password");
+    }
+
+    #[test]
+    fn encodes_back_to_same_string() {
+        let encoded_password = "$a0402$AAECAwQFBgcICQoLDA0ODw==$i6PwKXRFGHVv2momDm8VxQ==";
+
+        assert_eq!(SCryptHash::decode(encoded_password).unwrap().encode(), encoded_password);
+    }
+
+    #[test]
+    fn fails_on_wrong_amount_of_parts() {
+        assert!(SCryptHash::decode("$e0801$c29tZXNhbHQ=").is_none());
+        assert!(SCryptHash::decode("$e0801$c29tZXNhbHQ=$cGFzc3dvcmQ=$").is_none());
+    }
+
+    #[test]
+    fn fails_on_params_needing_too_much_memory() {
+        // log2(N) of 30 with r of 255 would need 32 TiB
+        assert!(SCryptHash::decode("$1eff01$c29tZXNhbHQ=$cGFzc3dvcmQ=").is_none());
+        assert!(SCryptHash::decode("$150801$c29tZXNhbHQ=$cGFzc3dvcmQ=").is_none());
+        assert!(SCryptHash::decode("$140801$c29tZXNhbHQ=$cGFzc3dvcmQ=").is_some());
+    }
+
+    #[test]
+    fn fails_on_params_not_being_hex() {
+        assert!(SCryptHash::decode("$xyz$c29tZXNhbHQ=$cGFzc3dvcmQ=").is_none());
+    }
+
+    #[test]
+    fn fails_on_cpu_cost_out_of_range() {
+        // N = 2^0
+        assert!(SCryptHash::decode("$801$c29tZXNhbHQ=$cGFzc3dvcmQ=").is_none());
+        // N = 2^31
+        assert!(SCryptHash::decode("$1f0801$c29tZXNhbHQ=$cGFzc3dvcmQ=").is_none());
+        // N = 2^65535
+        assert!(SCryptHash::decode("$ffff0801$c29tZXNhbHQ=$cGFzc3dvcmQ=").is_none());
+        // N = 2^16 with r = 1
+        assert!(SCryptHash::decode("$100101$c29tZXNhbHQ=$cGFzc3dvcmQ=").is_none());
+    }
+
+    #[test]
+    fn fails_on_memory_cost_out_of_range() {
+        assert!(SCryptHash::decode("$e0001$c29tZXNhbHQ=$cGFzc3dvcmQ=").is_none());
+    }
+
+    #[test]
+    fn fails_on_parallelization_out_of_range() {
+        assert!(SCryptHash::decode("$e0800$c29tZXNhbHQ=$cGFzc3dvcmQ=").is_none());
+    }
+
+    #[test]
+    fn fails_on_malformed_base64() {
+        assert!(SCryptHash::decode("$e0801$not base64$cGFzc3dvcmQ=").is_none());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PasswordEncoder, SCryptPasswordEncoder};
+    use crate::error::PasswordEncoderError;
+
+    #[test]
+    fn matches_default_hash() {
+        let encoder: SCryptPasswordEncoder = Default::default();
+
+        let unencoded_password = String::from("password");
+        let encoded_password =
+            String::from("$e0801$AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8gISIjJCUmJygpKissLS4vMDEyMzQ1Njc4OTo7PD0+Pw==$SNjiD+Mi6ArATkQKmDWdB+boUwy1pEiiq4yCoxxJoVM=");
+
+        assert!(encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+    }
+
+    #[test]
+    fn no_match_for_wrong_password() {
+        let encoder: SCryptPasswordEncoder = Default::default();
+
+        let unencoded_password = String::from("wrongpassword");
+        let encoded_password =
+            String::from("$e0801$AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8gISIjJCUmJygpKissLS4vMDEyMzQ1Njc4OTo7PD0+Pw==$SNjiD+Mi6ArATkQKmDWdB+boUwy1pEiiq4yCoxxJoVM=");
+
+        assert!(!encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+    }
+
+    #[test]
+    fn matches_hash_with_different_params() {
+        let encoder: SCryptPasswordEncoder = Default::default();
+
+        let unencoded_password = String::from("password");
+        let encoded_password = String::from("$100801$AAECAwQFBgcICQoLDA0ODw==$jWPkcxERY25E9gwism7ggXZkARLbUPyOZiOM5ZQx95s=");
+
+        assert!(encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+    }
+
+    #[test]
+    fn matches_only_with_configured_key_length() {
+        let unencoded_password = String::from("password");
+        let encoded_password = String::from("$a0402$AAECAwQFBgcICQoLDA0ODw==$i6PwKXRFGHVv2momDm8VxQ==");
+
+        let default_encoder: SCryptPasswordEncoder = Default::default();
+        assert!(!default_encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+
+        let encoder = SCryptPasswordEncoder::new(1024, 4, 2, 16, 16).unwrap();
+        assert!(encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+    }
+
+    #[test]
+    fn no_match_for_out_of_range_params() {
+        let encoder: SCryptPasswordEncoder = Default::default();
+
+        let unencoded_password = String::from("password");
+        let encoded_password = String::from("$ffffffff$AAECAwQFBgcICQoLDA0ODw==$jWPkcxERY25E9gwism7ggXZkARLbUPyOZiOM5ZQx95s=");
+
+        assert!(!encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+    }
+
+    #[test]
+    fn no_match_for_empty_hash() {
+        let encoder: SCryptPasswordEncoder = Default::default();
+
+        assert!(!encoder.matches_spring_security_hash(&String::from("password"), ""));
+        assert_eq!(encoder.try_matches_spring_security_hash("password", ""), Err(PasswordEncoderError::MalformedHash));
+    }
+
+    #[test]
+    fn encode_with_custom_params() {
+        let encoder = SCryptPasswordEncoder::new(1024, 4, 2, 16, 16).unwrap();
+
+        let unencoded_password = String::from("password");
+        let encoded_password = encoder.encode_spring_security_hash(&unencoded_password).unwrap();
+
+        assert!(encoded_password.starts_with("$a0402$"));
+        assert!(encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+    }
+
+    #[test]
+    fn constructor_rejects_invalid_params() {
+        assert!(SCryptPasswordEncoder::new(1, 8, 1, 32, 64).is_none());
+        assert!(SCryptPasswordEncoder::new(1000, 8, 1, 32, 64).is_none());
+        assert!(SCryptPasswordEncoder::new(131072, 1, 1, 32, 64).is_none());
+        assert!(SCryptPasswordEncoder::new(16384, 0, 1, 32, 64).is_none());
+        assert!(SCryptPasswordEncoder::new(16384, 8, 0, 32, 64).is_none());
+        assert!(SCryptPasswordEncoder::new(16384, 8, 1, 0, 64).is_none());
+        assert!(SCryptPasswordEncoder::new(16384, 8, 1, 32, 0).is_none());
+    }
+
+    #[test]
+    fn rejects_parameters_which_can_not_be_stored() {
+        assert!(SCryptPasswordEncoder::new(16384, 256, 1, 32, 64).is_none());
+        assert!(SCryptPasswordEncoder::new(1024, 8, 256, 32, 64).is_none());
+        assert!(SCryptPasswordEncoder::new(1024, 255, 255, 32, 64).is_some());
+        // 2 GiB of memory
+        assert!(SCryptPasswordEncoder::new(1 << 21, 8, 1, 32, 64).is_none());
+        assert!(SCryptPasswordEncoder::new(1 << 20, 8, 1, 32, 64).is_some());
+    }
+
+    #[test]
+    fn upgrade_encoding_for_lower_costs() {
+        let encoder: SCryptPasswordEncoder = Default::default();
+
+        assert!(!encoder.upgrade_encoding("$100801$AAECAwQFBgcICQoLDA0ODw==$jWPkcxERY25E9gwism7ggXZkARLbUPyOZiOM5ZQx95s="));
+        // lower cpu cost
+        assert!(encoder.upgrade_encoding("$d0801$AAECAwQFBgcICQoLDA0ODw==$jWPkcxERY25E9gwism7ggXZkARLbUPyOZiOM5ZQx95s="));
+        // lower memory cost
+        assert!(encoder.upgrade_encoding("$100401$AAECAwQFBgcICQoLDA0ODw==$jWPkcxERY25E9gwism7ggXZkARLbUPyOZiOM5ZQx95s="));
+    }
+
+    #[test]
+    fn upgrade_encoding_for_lower_parallelization() {
+        let encoder = SCryptPasswordEncoder::new(16384, 8, 2, 32, 16).unwrap();
+
+        assert!(encoder.upgrade_encoding("$e0801$AAECAwQFBgcICQoLDA0ODw==$jWPkcxERY25E9gwism7ggXZkARLbUPyOZiOM5ZQx95s="));
+        assert!(!encoder.upgrade_encoding("$e0802$AAECAwQFBgcICQoLDA0ODw==$jWPkcxERY25E9gwism7ggXZkARLbUPyOZiOM5ZQx95s="));
+    }
+
+    #[test]
+    fn upgrade_encoding_for_empty_or_malformed_hash() {
+        let encoder: SCryptPasswordEncoder = Default::default();
+
+        assert_eq!(encoder.try_upgrade_encoding(""), Ok(false));
+        assert_eq!(encoder.try_upgrade_encoding("$100801$AAECAwQFBgcICQoLDA0ODw=="), Err(PasswordEncoderError::MalformedHash));
+    }
+
+    #[test]
+    fn encode_spring_security_v5_8() {
+        let encoder = SCryptPasswordEncoder::defaults_for_spring_security_v5_8();
+
+        let encoded_password = encoder.encode_spring_security_hash("password").unwrap();
+
+        assert!(encoded_password.starts_with("$100801$"));
+        // 16 bytes salt
+        assert_eq!(encoded_password.split('$').nth(2).unwrap().len(), 24);
+        assert!(encoder.matches_spring_security_hash("password", &encoded_password));
+        assert!(!encoder.upgrade_encoding(&encoded_password));
+        assert!(SCryptPasswordEncoder::defaults_for_spring_security_v4_1().matches_spring_security_hash("password", &encoded_password));
+    }
+}
@@ -0,0 +1,47 @@
+use crate::encoder::md::MessageDigestPasswordEncoder;
+use sha1::Sha1;
+
+// https://github.com/spring-projects/spring-security/blob/0e3ee8990299436223c3037afb096f454f17ef5e/crypto/src/main/java/org/springframework/security/crypto/password/MessageDigestPasswordEncoder.java
+pub type Sha1PasswordEncoder = MessageDigestPasswordEncoder<Sha1>;
+
+#[cfg(test)]
+mod tests {
+    use super::Sha1PasswordEncoder;
+    use crate::PasswordEncoder;
+
+    #[test]
+    fn matches_correct_password_with_salt() {
+        let encoder: Sha1PasswordEncoder = Default::default();
+
+        let unencoded_password = String::from("password");
+        let encoded_password = String::from("{thisissalt}ab2d02c9b8a9dcc678814c9270cdf932b6fb16c4");
+
+        let result = encoder.matches_spring_security_hash(&unencoded_password, &encoded_password);
+
+        assert!(result);
+    }
+
+    #[test]
+    fn matches_correct_password_no_salt() {
+        let encoder: Sha1PasswordEncoder = Default::default();
+
+        let unencoded_password = String::from("password");
+        let encoded_password = String::from("5baa61e4c9b93f3f0682250b6cf8331b7ee68fd8");
+
+        let result = encoder.matches_spring_security_hash(&unencoded_password, &encoded_password);
+
+        assert!(result);
+    }
+
+    #[test]
+    fn no_match_for_wrong_password() {
+        let encoder: Sha1PasswordEncoder = Default::default();
+
+        let unencoded_password = String::from("wrongpassword");
+        let encoded_password = String::from("{thisissalt}ab2d02c9b8a9dcc678814c9270cdf932b6fb16c4");
+
+        let result = encoder.matches_spring_security_hash(&unencoded_password, &encoded_password);
+
+        assert!(!result);
+    }
+}
@@ -1,4 +1,47 @@
-#[derive(Debug)]
-pub struct Sha256PasswordEncoder {
-    iterations: u32,
+use crate::encoder::md::MessageDigestPasswordEncoder;
+use sha2::Sha256;
+
+// https://github.com/spring-projects/spring-security/blob/0e3ee8990299436223c3037afb096f454f17ef5e/crypto/src/main/java/org/springframework/security/crypto/password/MessageDigestPasswordEncoder.java
+pub type Sha256PasswordEncoder = MessageDigestPasswordEncoder<Sha256>;
+
+#[cfg(test)]
+mod tests {
+    use super::Sha256PasswordEncoder;
+    use crate::PasswordEncoder;
+
+    #[test]
+    fn matches_correct_password_with_salt() {
+        let encoder: Sha256PasswordEncoder = Default::default();
+
+        let unencoded_password = String::from("password");
+        let encoded_password = String::from("{thisissalt}3ffac017def094e1cd0dba405566ad658b3e6b2585b9b814fd35da47ae528262");
+
+        let result = encoder.matches_spring_security_hash(&unencoded_password, &encoded_password);
+
+        assert!(result);
+    }
+
+    #[test]
+    fn matches_correct_password_no_salt() {
+        let encoder: Sha256PasswordEncoder = Default::default();
+
+        let unencoded_password = String::from("password");
+        let encoded_password = String::from("5e884898da28047151d0e56f8dc6292773603d0d6aabbdd62a11ef721d1542d8");
+
+        let result = encoder.matches_spring_security_hash(&unencoded_password, &encoded_password);
+
+        assert!(result);
+    }
+
+    #[test]
+    fn no_match_for_wrong_password() {
+        let encoder: Sha256PasswordEncoder = Default::default();
+
+        let unencoded_password = String::from("wrongpassword");
+        let encoded_password = String::from("{thisissalt}3ffac017def094e1cd0dba405566ad658b3e6b2585b9b814fd35da47ae528262");
+
+        let result = encoder.matches_spring_security_hash(&unencoded_password, &encoded_password);
+
+        assert!(!result);
+    }
 }
@@ -1,13 +1,145 @@
-#[derive(Debug)]
+use crate::encoder::md::iterated_digest;
+use crate::error::PasswordEncoderError;
+use crate::keygen::secure_random_bytes;
+use crate::password::SecretPassword;
+use crate::util::constant_time_equals;
+use crate::PasswordEncoder;
+use sha2::Sha256;
+use zeroize::Zeroizing;
+
+// https://github.com/spring-projects/spring-security/blob/0e3ee8990299436223c3037afb096f454f17ef5e/crypto/src/main/java/org/springframework/security/crypto/keygen/KeyGenerators.java#L35
+pub(crate) const SALT_LENGTH: usize = 8;
+
+#[derive(Clone, Debug)]
 pub struct StandardPasswordEncoder {
     iterations: u32,
+    secret: SecretPassword,
 }
 
 impl Default for StandardPasswordEncoder {
     fn default() -> StandardPasswordEncoder {
+        StandardPasswordEncoder::new("")
+    }
+}
+
+impl StandardPasswordEncoder {
+    /// Uses the site-wide secret, which gets hashed between salt and password
+    pub fn new(secret: &str) -> StandardPasswordEncoder {
         StandardPasswordEncoder {
             // https://github.com/spring-projects/spring-security/blob/0e3ee8990299436223c3037afb096f454f17ef5e/crypto/src/main/java/org/springframework/security/crypto/password/StandardPasswordEncoder.java#LL53C48-L53C52
-            iterations: 1024
+            iterations: 1024,
+            secret: SecretPassword::from(secret),
+        }
+    }
+
+    // returns the salt followed by the digest of salt, secret and password
+    fn digest(&self, unencoded_password: &[u8], salt: &[u8]) -> Zeroizing<Vec<u8>> {
+        let mut value = Zeroizing::new(Vec::with_capacity(salt.len() + self.secret.as_bytes().len() + unencoded_password.len()));
+        value.extend_from_slice(salt);
+        value.extend_from_slice(self.secret.as_bytes());
+        value.extend_from_slice(unencoded_password);
+
+        let digest = iterated_digest::<Sha256>(&value, self.iterations);
+        let mut result = Zeroizing::new(Vec::with_capacity(salt.len() + digest.len()));
+        result.extend_from_slice(salt);
+        result.extend_from_slice(&digest);
+        result
+    }
+}
+
+impl PasswordEncoder for StandardPasswordEncoder {
+    fn matches_spring_security_hash(&self, unencoded_password: &str, encoded_password: &str) -> bool {
+        self.try_matches_spring_security_hash(unencoded_password, encoded_password).unwrap_or(false)
+    }
+
+    fn encode_spring_security_hash(&self, unencoded_password: &str) -> Option<String> {
+        self.try_encode_spring_security_hash(unencoded_password).ok()
+    }
+
+    fn try_matches_spring_security_hash_bytes(&self, unencoded_password: &[u8], encoded_password: &str) -> Result<bool, PasswordEncoderError> {
+        let digested = hex::decode(encoded_password).map_err(|_| PasswordEncoderError::MalformedHash)?;
+        if digested.len() < SALT_LENGTH {
+            return Err(PasswordEncoderError::MalformedHash);
         }
+
+        Ok(constant_time_equals(&digested, &self.digest(unencoded_password, &digested[..SALT_LENGTH])))
+    }
+
+    fn try_encode_spring_security_hash_bytes(&self, unencoded_password: &[u8]) -> Result<String, PasswordEncoderError> {
+        let salt = secure_random_bytes(SALT_LENGTH)?;
+        Ok(hex::encode(self.digest(unencoded_password, &salt)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PasswordEncoder, StandardPasswordEncoder};
+    use crate::error::PasswordEncoderError;
+
+    #[test]
+    fn matches_without_secret() {
+        let encoder: StandardPasswordEncoder = Default::default();
+
+        let unencoded_password = String::from("password");
+        let encoded_password = String::from("010203040506070820750a715c83fd40289f5e08d0fa5fd4b02509ff0bc670c7f163ef9d5d24ad8d");
+
+        assert!(encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+    }
+
+    #[test]
+    fn matches_with_secret() {
+        let encoder = StandardPasswordEncoder::new("secret");
+
+        let unencoded_password = String::from("password");
+        let encoded_password = String::from("0102030405060708b43dcfb7c62c5f43adc9947ef59c831091a294091212c112aacbb0b01aa51a45");
+
+        assert!(encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+    }
+
+    #[test]
+    fn no_match_for_wrong_secret() {
+        let encoder: StandardPasswordEncoder = Default::default();
+
+        let unencoded_password = String::from("password");
+        let encoded_password = String::from("0102030405060708b43dcfb7c62c5f43adc9947ef59c831091a294091212c112aacbb0b01aa51a45");
+
+        assert!(!encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+    }
+
+    #[test]
+    fn no_match_for_wrong_password() {
+        let encoder = StandardPasswordEncoder::new("secret");
+
+        let unencoded_password = String::from("wrongpassword");
+        let encoded_password = String::from("0102030405060708b43dcfb7c62c5f43adc9947ef59c831091a294091212c112aacbb0b01aa51a45");
+
+        assert!(!encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
+    }
+
+    #[test]
+    fn no_match_for_too_short_hash() {
+        let encoder: StandardPasswordEncoder = Default::default();
+
+        assert!(!encoder.matches_spring_security_hash(&String::from("password"), &String::from("010203")));
+    }
+
+    #[test]
+    fn error_for_malformed_hash() {
+        let encoder: StandardPasswordEncoder = Default::default();
+
+        assert_eq!(encoder.try_matches_spring_security_hash("password", "not hex"), Err(PasswordEncoderError::MalformedHash));
+        assert_eq!(encoder.try_matches_spring_security_hash("password", "010203"), Err(PasswordEncoderError::MalformedHash));
+    }
+
+    #[test]
+    fn encode_with_secret() {
+        let encoder = StandardPasswordEncoder::new("secret");
+
+        let unencoded_password = String::from("password");
+        let encoded_password = encoder.encode_spring_security_hash(&unencoded_password).unwrap();
+
+        // 8 bytes salt and 32 bytes digest
+        assert_eq!(encoded_password.len(), 80);
+        assert!(encoder.matches_spring_security_hash(&unencoded_password, &encoded_password));
     }
 }
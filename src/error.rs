@@ -0,0 +1,72 @@
+use std::error::Error;
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PasswordEncoderError {
+    /// The encoded password does not start with an id, like `{bcrypt}`
+    MissingEncoderId,
+    /// The encoded password starts with an id no encoder is mapped for
+    UnmappedEncoderId(String),
+    /// The encoded password can not be decoded, like bad hex, bad Base64 or a broken hash format
+    MalformedHash,
+    /// The encoded password uses an algorithm the encoder does not support, like `{MD5}` for LDAP
+    UnsupportedAlgorithm(String),
+    /// The parameters of the encoder or the encoded password can not be used for hashing
+    InvalidParameters,
+    /// The random source failed to generate a salt
+    RandomSourceFailure,
+    /// The password is given as bytes, but the encoder only supports UTF-8 passwords
+    InvalidUtf8Password,
+    /// Encoding failed without telling why, only used for encoders not implementing the fallible API
+    EncodingFailed,
+    /// The encoded password was peppered with a key that is not known (anymore)
+    UnmappedKeyId(String),
+    /// The pepper key could not be loaded from the named environment variable or file
+    KeyUnavailable(String),
+}
+
+impl fmt::Display for PasswordEncoderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // same messages as the IllegalArgumentException thrown by spring security
+        // https://github.com/spring-projects/spring-security/blob/0e3ee8990299436223c3037afb096f454f17ef5e/crypto/src/main/java/org/springframework/security/crypto/password/DelegatingPasswordEncoder.java#L310-L316
+        match self {
+            PasswordEncoderError::MissingEncoderId => {
+                write!(
+                    f,
+                    "You have entered a password with no PasswordEncoder. If that is your intent, it should be prefixed with `{{noop}}`."
+                )
+            }
+            PasswordEncoderError::UnmappedEncoderId(id) => write!(f, "There is no PasswordEncoder mapped for the id \"{}\"", id),
+            PasswordEncoderError::MalformedHash => write!(f, "Encoded password does not look like a hash of this encoder"),
+            PasswordEncoderError::UnsupportedAlgorithm(algorithm) => write!(f, "Encoded password uses the unsupported algorithm \"{}\"", algorithm),
+            PasswordEncoderError::InvalidParameters => write!(f, "Parameters can not be used for hashing"),
+            PasswordEncoderError::RandomSourceFailure => write!(f, "Random source failed to generate a salt"),
+            PasswordEncoderError::InvalidUtf8Password => write!(f, "Password is not valid UTF-8"),
+            PasswordEncoderError::EncodingFailed => write!(f, "Password could not be encoded"),
+            PasswordEncoderError::UnmappedKeyId(id) => write!(f, "There is no pepper key mapped for the id \"{}\"", id),
+            PasswordEncoderError::KeyUnavailable(source) => write!(f, "Pepper key could not be loaded from \"{}\"", source),
+        }
+    }
+}
+
+impl Error for PasswordEncoderError {}
+
+#[cfg(test)]
+mod tests {
+    use super::PasswordEncoderError;
+
+    #[test]
+    fn displays_unmapped_id() {
+        let error = PasswordEncoderError::UnmappedEncoderId(String::from("foo"));
+
+        assert_eq!(error.to_string(), "There is no PasswordEncoder mapped for the id \"foo\"");
+    }
+
+    #[test]
+    fn displays_missing_id() {
+        let error = PasswordEncoderError::MissingEncoderId;
+
+        assert!(error.to_string().ends_with("it should be prefixed with `{noop}`."));
+    }
+}
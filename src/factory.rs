@@ -0,0 +1,141 @@
+use crate::encoder::argon2::Argon2PasswordEncoder;
+use crate::encoder::bcrypt::BCryptPasswordEncoder;
+use crate::encoder::delegating::DelegatingPasswordEncoder;
+use crate::encoder::ldap::LdapShaPasswordEncoder;
+use crate::encoder::md4::Md4PasswordEncoder;
+use crate::encoder::md5::Md5PasswordEncoder;
+use crate::encoder::noop::NoOpPasswordEncoder;
+use crate::encoder::onion::{BCryptMd4PasswordEncoder, BCryptMd5PasswordEncoder};
+use crate::encoder::pbkdf2::Pbkdf2PasswordEncoder;
+use crate::encoder::scrypt::SCryptPasswordEncoder;
+use crate::encoder::sha1::Sha1PasswordEncoder;
+use crate::encoder::sha256::Sha256PasswordEncoder;
+use crate::encoder::standard::StandardPasswordEncoder;
+use crate::error::PasswordEncoderError;
+use crate::{Encoder, PasswordEncoder};
+use std::collections::HashMap;
+
+impl Encoder {
+    /// Creates the encoder with its default configuration, like the ones registered by `PasswordEncoderFactories`
+    pub fn create_password_encoder(&self) -> Box<dyn PasswordEncoder + Send + Sync> {
+        match self {
+            Encoder::ARGON2 => Box::new(Argon2PasswordEncoder::defaults_for_spring_security_v5_2()),
+            Encoder::ARGON2V5_8 => Box::new(Argon2PasswordEncoder::defaults_for_spring_security_v5_8()),
+            Encoder::BCRYPT => Box::<BCryptPasswordEncoder>::default(),
+            Encoder::BCRYPTMD4 => Box::<BCryptMd4PasswordEncoder>::default(),
+            Encoder::BCRYPTMD5 => Box::<BCryptMd5PasswordEncoder>::default(),
+            Encoder::LDAP => Box::<LdapShaPasswordEncoder>::default(),
+            Encoder::MD4 => Box::<Md4PasswordEncoder>::default(),
+            Encoder::MD5 => Box::<Md5PasswordEncoder>::default(),
+            Encoder::NOOP => Box::<NoOpPasswordEncoder>::default(),
+            Encoder::PBKDF2 => Box::new(Pbkdf2PasswordEncoder::defaults_for_spring_security_v5_5()),
+            Encoder::PBKDF2V5_8 => Box::new(Pbkdf2PasswordEncoder::defaults_for_spring_security_v5_8()),
+            Encoder::SCRYPT => Box::new(SCryptPasswordEncoder::defaults_for_spring_security_v4_1()),
+            Encoder::SCRYPTV5_8 => Box::new(SCryptPasswordEncoder::defaults_for_spring_security_v5_8()),
+            Encoder::SHA1 => Box::<Sha1PasswordEncoder>::default(),
+            Encoder::SHA256 => Box::<Sha256PasswordEncoder>::default(),
+            Encoder::STANDARD => Box::<StandardPasswordEncoder>::default(),
+            Encoder::DELEGATING => Box::<DelegatingPasswordEncoder>::default(),
+        }
+    }
+}
+
+/// Case-insensitive names of encoders, like algorithm names in configuration files
+#[derive(Clone, Debug)]
+pub struct EncoderAliases {
+    // keys are lower-cased
+    aliases: HashMap<String, Encoder>,
+}
+
+impl Default for EncoderAliases {
+    /// Knows the ids of all encoders, ignoring case
+    fn default() -> EncoderAliases {
+        let mut aliases = EncoderAliases { aliases: HashMap::new() };
+        for encoder in Encoder::ALL {
+            aliases.add_alias(encoder.id(), encoder);
+        }
+        aliases
+    }
+}
+
+impl EncoderAliases {
+    /// Knows no names at all, not even the ids
+    pub fn empty() -> EncoderAliases {
+        EncoderAliases { aliases: HashMap::new() }
+    }
+
+    /// Replaces any encoder known by the same name
+    pub fn add_alias(&mut self, alias: &str, encoder: Encoder) {
+        self.aliases.insert(alias.to_lowercase(), encoder);
+    }
+
+    pub fn with_alias(mut self, alias: &str, encoder: Encoder) -> Self {
+        self.add_alias(alias, encoder);
+        self
+    }
+
+    pub fn parse(&self, name: &str) -> Result<Encoder, PasswordEncoderError> {
+        self.aliases
+            .get(&name.to_lowercase())
+            .copied()
+            .ok_or_else(|| PasswordEncoderError::UnmappedEncoderId(name.to_string()))
+    }
+
+    pub fn create_password_encoder(&self, name: &str) -> Result<Box<dyn PasswordEncoder + Send + Sync>, PasswordEncoderError> {
+        self.parse(name).map(|encoder| encoder.create_password_encoder())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EncoderAliases;
+    use crate::error::PasswordEncoderError;
+    use crate::Encoder;
+
+    #[test]
+    fn parses_ids_ignoring_case() {
+        let aliases: EncoderAliases = Default::default();
+
+        assert_eq!(aliases.parse("BCrypt"), Ok(Encoder::BCRYPT));
+        assert_eq!(aliases.parse("md5"), Ok(Encoder::MD5));
+        assert_eq!(aliases.parse("sha-1"), Ok(Encoder::SHA1));
+        // different encoders in spring security
+        assert_eq!(aliases.parse("sha-256"), Ok(Encoder::SHA256));
+        assert_eq!(aliases.parse("SHA256"), Ok(Encoder::STANDARD));
+    }
+
+    #[test]
+    fn parses_added_aliases() {
+        let aliases = EncoderAliases::default().with_alias("Legacy", Encoder::MD5).with_alias("sha256", Encoder::SHA256);
+
+        assert_eq!(aliases.parse("legacy"), Ok(Encoder::MD5));
+        assert_eq!(aliases.parse("LEGACY"), Ok(Encoder::MD5));
+        assert_eq!(aliases.parse("sha256"), Ok(Encoder::SHA256));
+    }
+
+    #[test]
+    fn error_for_unknown_name() {
+        let aliases = EncoderAliases::empty().with_alias("legacy", Encoder::MD5);
+
+        assert_eq!(aliases.parse("bcrypt"), Err(PasswordEncoderError::UnmappedEncoderId(String::from("bcrypt"))));
+        assert!(aliases.create_password_encoder("bcrypt").is_err());
+    }
+
+    #[test]
+    fn creates_encoder_by_name() {
+        let aliases: EncoderAliases = Default::default();
+        let encoder = aliases.create_password_encoder("NOOP").unwrap();
+
+        assert_eq!(encoder.encode_spring_security_hash("password"), Some(String::from("password")));
+    }
+
+    #[test]
+    fn created_encoders_match_their_own_hashes() {
+        for encoder in Encoder::ALL {
+            let password_encoder = encoder.create_password_encoder();
+            let encoded_password = password_encoder.encode_spring_security_hash("password").unwrap();
+
+            assert!(password_encoder.matches_spring_security_hash("password", &encoded_password), "{}", encoder);
+        }
+    }
+}
@@ -0,0 +1,551 @@
+use crate::codec::{BASE64, BCRYPT_BASE64};
+use crate::encoder::argon2::Argon2Hash;
+use crate::encoder::bcrypt::{decode_version_and_cost, is_bcrypt_hash, BCryptVersion};
+use crate::encoder::delegating::{get_encoder_id_from_encoded_password, without_delegation_marker};
+use crate::encoder::ldap;
+use crate::encoder::md::{extract_salt, LegacyDigestEncoder};
+use crate::encoder::md4::Md4PasswordEncoder;
+use crate::encoder::md5::Md5PasswordEncoder;
+use crate::encoder::pbkdf2::Pbkdf2PasswordEncoder;
+use crate::encoder::scrypt::SCryptHash;
+use crate::encoder::standard;
+use crate::error::PasswordEncoderError;
+use crate::Encoder;
+use base64::Engine;
+use digest::Digest;
+use md4::Md4;
+use md5::Md5;
+use sha1::Sha1;
+use sha2::Sha256;
+
+// bcrypt salt in radix-64, like the 22 characters of a bcrypt salt string
+const BCRYPT_SALT_LENGTH: usize = 22;
+
+/// How salt and digest are written into the encoded password
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HashEncoding {
+    Hex,
+    Base64,
+    /// The radix-64 alphabet of bcrypt, starting with `./`
+    BCryptBase64,
+    /// Not hashed at all, like `{noop}` or plaintext LDAP passwords
+    Plain,
+}
+
+/// Parameters stored inside the encoded password, encoders storing no parameters use `None`
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum HashParameters {
+    None,
+    /// Version is missing for the legacy `$2$` prefix
+    BCrypt {
+        version: Option<BCryptVersion>,
+        cost: u32,
+    },
+    /// Memory is given in KiB
+    Argon2 {
+        algorithm: String,
+        version: u32,
+        memory: u32,
+        iterations: u32,
+        parallelism: u32,
+    },
+    SCrypt {
+        cpu_cost: u32,
+        memory_cost: u32,
+        parallelization: u32,
+    },
+    /// Not stored by Spring Security, taken from the encoder. Missing when the hash is not as long as the encoder
+    /// writes them, the salt is empty and the digest length covers salt and digest then.
+    Pbkdf2 {
+        salt_length: Option<usize>,
+    },
+}
+
+/// Everything stored in an encoded password besides the hash itself, found without knowing the password
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct HashInfo {
+    pub encoder: Encoder,
+    pub parameters: HashParameters,
+    /// Empty for unsalted hashes, message digest encoders hash the salt as text so its characters are returned
+    pub salt: Vec<u8>,
+    /// In bytes, or the length of the password when it is not hashed
+    pub digest_length: usize,
+    pub encoding: HashEncoding,
+}
+
+impl HashInfo {
+    /// Parses an encoded password as stored by the delegating password encoder, like `{bcrypt}$2a$10$...`
+    pub fn parse(encoded_password: &str) -> Result<HashInfo, PasswordEncoderError> {
+        let encoder_id = get_encoder_id_from_encoded_password(encoded_password, "{", "}").ok_or(PasswordEncoderError::MissingEncoderId)?;
+        let encoder = match encoder_id.parse::<Encoder>() {
+            // spring security maps no id to the delegating encoder itself
+            Ok(Encoder::DELEGATING) | Err(_) => return Err(PasswordEncoderError::UnmappedEncoderId(encoder_id)),
+            Ok(encoder) => encoder,
+        };
+        HashInfo::parse_for(encoder, &without_delegation_marker(encoded_password, &encoder_id, "{", "}"))
+    }
+
+    /// Parses an encoded password of the given encoder, without any `{id}` in front
+    pub fn parse_for(encoder: Encoder, encoded_password: &str) -> Result<HashInfo, PasswordEncoderError> {
+        match encoder {
+            Encoder::ARGON2 | Encoder::ARGON2V5_8 => parse_argon2(encoder, encoded_password),
+            Encoder::BCRYPT => parse_bcrypt(encoded_password),
+            Encoder::BCRYPTMD4 | Encoder::BCRYPTMD5 => parse_onion(encoder, encoded_password),
+            Encoder::LDAP => parse_ldap(encoded_password),
+            Encoder::MD4 => parse_message_digest(Encoder::MD4, <Md4 as Digest>::output_size(), encoded_password),
+            Encoder::MD5 => parse_message_digest(Encoder::MD5, <Md5 as Digest>::output_size(), encoded_password),
+            Encoder::NOOP => Ok(HashInfo::plain(Encoder::NOOP, encoded_password)),
+            Encoder::PBKDF2 => parse_pbkdf2(encoder, &Pbkdf2PasswordEncoder::defaults_for_spring_security_v5_5(), encoded_password),
+            Encoder::PBKDF2V5_8 => parse_pbkdf2(encoder, &Pbkdf2PasswordEncoder::defaults_for_spring_security_v5_8(), encoded_password),
+            Encoder::SCRYPT | Encoder::SCRYPTV5_8 => parse_scrypt(encoder, encoded_password),
+            Encoder::SHA1 => parse_message_digest(Encoder::SHA1, <Sha1 as Digest>::output_size(), encoded_password),
+            Encoder::SHA256 => parse_message_digest(Encoder::SHA256, <Sha256 as Digest>::output_size(), encoded_password),
+            Encoder::STANDARD => parse_standard(encoded_password),
+            Encoder::DELEGATING => HashInfo::parse(encoded_password),
+        }
+    }
+
+    /// Parses an encoded password of a configured PBKDF2 encoder, without any `{id}` in front, as salt length and
+    /// hash width are not stored in the hash. It is reported as `Encoder::PBKDF2`.
+    pub fn parse_for_pbkdf2(pbkdf2_encoder: &Pbkdf2PasswordEncoder, encoded_password: &str) -> Result<HashInfo, PasswordEncoderError> {
+        parse_pbkdf2(Encoder::PBKDF2, pbkdf2_encoder, encoded_password)
+    }
+
+    fn plain(encoder: Encoder, encoded_password: &str) -> HashInfo {
+        HashInfo {
+            encoder,
+            parameters: HashParameters::None,
+            salt: vec![],
+            digest_length: encoded_password.len(),
+            encoding: HashEncoding::Plain,
+        }
+    }
+}
+
+fn parse_argon2(encoder: Encoder, encoded_password: &str) -> Result<HashInfo, PasswordEncoderError> {
+    let decoded = Argon2Hash::decode(encoded_password).ok_or(PasswordEncoderError::MalformedHash)?;
+    Ok(HashInfo {
+        encoder,
+        parameters: HashParameters::Argon2 {
+            algorithm: decoded.algorithm.as_str().to_string(),
+            version: decoded.version as u32,
+            memory: decoded.memory,
+            iterations: decoded.iterations,
+            parallelism: decoded.parallelism,
+        },
+        digest_length: decoded.hash.len(),
+        salt: decoded.salt,
+        encoding: HashEncoding::Base64,
+    })
+}
+
+// "$" + version + "$" + two digit cost + "$" + 22 characters salt + 31 characters hash
+fn parse_bcrypt(encoded_password: &str) -> Result<HashInfo, PasswordEncoderError> {
+    if !is_bcrypt_hash(encoded_password) {
+        return Err(PasswordEncoderError::MalformedHash);
+    }
+    let (version, cost) = decode_version_and_cost(encoded_password).ok_or(PasswordEncoderError::MalformedHash)?;
+    let salt_and_hash = encoded_password.rsplit('$').next().ok_or(PasswordEncoderError::MalformedHash)?;
+    let (salt, hash) = salt_and_hash.split_at(BCRYPT_SALT_LENGTH);
+
+    Ok(HashInfo {
+        encoder: Encoder::BCRYPT,
+        parameters: HashParameters::BCrypt { version, cost },
+        salt: BCRYPT_BASE64.decode(salt).map_err(|_| PasswordEncoderError::MalformedHash)?,
+        digest_length: BCRYPT_BASE64.decode(hash).map_err(|_| PasswordEncoderError::MalformedHash)?.len(),
+        encoding: HashEncoding::BCryptBase64,
+    })
+}
+
+// the salt of the legacy digest stays in front of the bcrypt hash, its parameters are the ones of bcrypt,
+// the salt markers are the ones of the legacy encoder registered for the id
+fn parse_onion(encoder: Encoder, encoded_password: &str) -> Result<HashInfo, PasswordEncoderError> {
+    let (_, bcrypt_hash) = match encoder {
+        Encoder::BCRYPTMD4 => Md4PasswordEncoder::default().split_salt(encoded_password),
+        _ => Md5PasswordEncoder::default().split_salt(encoded_password),
+    };
+    Ok(HashInfo {
+        encoder,
+        ..parse_bcrypt(bcrypt_hash)?
+    })
+}
+
+fn parse_ldap(encoded_password: &str) -> Result<HashInfo, PasswordEncoderError> {
+    let prefix = match ldap::extract_prefix(encoded_password)? {
+        Some(prefix) => prefix,
+        None => return Ok(HashInfo::plain(Encoder::LDAP, encoded_password)),
+    };
+    let encoded_password_without_prefix = &encoded_password[prefix.len()..];
+
+    let (salt, digest_length) = if ldap::is_ssha_prefix(prefix) {
+        let salt = ldap::extract_salt(encoded_password_without_prefix).ok_or(PasswordEncoderError::MalformedHash)?;
+        (salt, ldap::SHA_LENGTH)
+    } else if ldap::is_sha_prefix(prefix) {
+        let hash = BASE64.decode(encoded_password_without_prefix).map_err(|_| PasswordEncoderError::MalformedHash)?;
+        (vec![], hash.len())
+    } else {
+        return Err(PasswordEncoderError::UnsupportedAlgorithm(prefix.to_string()));
+    };
+
+    Ok(HashInfo {
+        encoder: Encoder::LDAP,
+        parameters: HashParameters::None,
+        salt,
+        digest_length,
+        encoding: HashEncoding::Base64,
+    })
+}
+
+// the salt markers and the encoding of the default message digest encoders, the digest length tells hex from Base64
+fn parse_message_digest(encoder: Encoder, output_size: usize, encoded_password: &str) -> Result<HashInfo, PasswordEncoderError> {
+    let salt = extract_salt("{", "}", encoded_password);
+    let encoded_digest = match salt {
+        Some(found_salt) => &encoded_password[(found_salt.len() + 2)..],
+        None => encoded_password,
+    };
+
+    let encoding = if encoded_digest.len() == output_size * 2 && hex::decode(encoded_digest).is_ok() {
+        HashEncoding::Hex
+    } else if BASE64.decode(encoded_digest).map(|digest| digest.len()) == Ok(output_size) {
+        HashEncoding::Base64
+    } else {
+        return Err(PasswordEncoderError::MalformedHash);
+    };
+
+    Ok(HashInfo {
+        encoder,
+        parameters: HashParameters::None,
+        salt: salt.map(|found_salt| found_salt.as_bytes().to_vec()).unwrap_or_default(),
+        digest_length: output_size,
+        encoding,
+    })
+}
+
+// salt length and hash width are not stored in the hash, they are taken from the encoder. The expected length tells hex
+// from Base64, other hashes are decoded as hex when possible and their split into salt and digest is unknown.
+fn parse_pbkdf2(encoder: Encoder, pbkdf2_encoder: &Pbkdf2PasswordEncoder, encoded_password: &str) -> Result<HashInfo, PasswordEncoderError> {
+    let salt_length = pbkdf2_encoder.salt_length();
+    let expected_length = salt_length + pbkdf2_encoder.hash_width() / 8;
+
+    let (digested, encoding) = match (hex::decode(encoded_password), BASE64.decode(encoded_password)) {
+        (_, Ok(digested)) if digested.len() == expected_length => (digested, HashEncoding::Base64),
+        (Ok(digested), _) => (digested, HashEncoding::Hex),
+        (Err(_), Ok(digested)) => (digested, HashEncoding::Base64),
+        (Err(_), Err(_)) => return Err(PasswordEncoderError::MalformedHash),
+    };
+    // the encoder fails to match hashes shorter than its salt
+    if digested.len() < salt_length {
+        return Err(PasswordEncoderError::MalformedHash);
+    }
+
+    let (salt_length, salt, digest_length) = if digested.len() == expected_length {
+        (Some(salt_length), digested[..salt_length].to_vec(), expected_length - salt_length)
+    } else {
+        (None, vec![], digested.len())
+    };
+    Ok(HashInfo {
+        encoder,
+        parameters: HashParameters::Pbkdf2 { salt_length },
+        salt,
+        digest_length,
+        encoding,
+    })
+}
+
+fn parse_scrypt(encoder: Encoder, encoded_password: &str) -> Result<HashInfo, PasswordEncoderError> {
+    let decoded = SCryptHash::decode(encoded_password).ok_or(PasswordEncoderError::MalformedHash)?;
+    Ok(HashInfo {
+        encoder,
+        parameters: HashParameters::SCrypt {
+            cpu_cost: 1 << decoded.log_cpu_cost,
+            memory_cost: decoded.memory_cost,
+            parallelization: decoded.parallelization,
+        },
+        digest_length: decoded.derived_key.len(),
+        salt: decoded.salt,
+        encoding: HashEncoding::Base64,
+    })
+}
+
+fn parse_standard(encoded_password: &str) -> Result<HashInfo, PasswordEncoderError> {
+    let digested = hex::decode(encoded_password).map_err(|_| PasswordEncoderError::MalformedHash)?;
+    if digested.len() < standard::SALT_LENGTH {
+        return Err(PasswordEncoderError::MalformedHash);
+    }
+
+    Ok(HashInfo {
+        encoder: Encoder::STANDARD,
+        parameters: HashParameters::None,
+        salt: digested[..standard::SALT_LENGTH].to_vec(),
+        digest_length: digested.len() - standard::SALT_LENGTH,
+        encoding: HashEncoding::Hex,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HashEncoding, HashInfo, HashParameters};
+    use crate::encoder::bcrypt::BCryptVersion;
+    use crate::encoder::pbkdf2::{Pbkdf2PasswordEncoder, SecretKeyFactoryAlgorithm};
+    use crate::error::PasswordEncoderError;
+    use crate::Encoder;
+
+    #[test]
+    fn parses_bcrypt() {
+        let info = HashInfo::parse("{bcrypt}$2a$10$dXJ3SW6G7P50lGmMkkmwe.20cQQubK3.HZWzG3YB1tlRy.fqvM/BG").unwrap();
+
+        assert_eq!(info.encoder, Encoder::BCRYPT);
+        assert_eq!(
+            info.parameters,
+            HashParameters::BCrypt {
+                version: Some(BCryptVersion::TwoA),
+                cost: 10
+            }
+        );
+        assert_eq!(info.salt.len(), 16);
+        assert_eq!(info.digest_length, 23);
+        assert_eq!(info.encoding, HashEncoding::BCryptBase64);
+    }
+
+    #[test]
+    fn parses_bcrypt_without_version() {
+        let info = HashInfo::parse_for(Encoder::BCRYPT, "$2$05$CCCCCCCCCCCCCCCCCCCCC.E5YPO9kmyuRGyh0XouQYb4YMJKvyOeW").unwrap();
+
+        assert_eq!(info.parameters, HashParameters::BCrypt { version: None, cost: 5 });
+    }
+
+    #[test]
+    fn parses_bcrypt_wrapped_md5() {
+        let info = HashInfo::parse("{bcrypt-md5}{thisissalt}$2a$10$dXJ3SW6G7P50lGmMkkmwe.20cQQubK3.HZWzG3YB1tlRy.fqvM/BG").unwrap();
+
+        assert_eq!(info.encoder, Encoder::BCRYPTMD5);
+        assert_eq!(
+            info.parameters,
+            HashParameters::BCrypt {
+                version: Some(BCryptVersion::TwoA),
+                cost: 10
+            }
+        );
+        assert_eq!(info.salt.len(), 16);
+        assert_eq!(
+            HashInfo::parse_for(Encoder::BCRYPTMD4, "$2$05$CCCCCCCCCCCCCCCCCCCCC.E5YPO9kmyuRGyh0XouQYb4YMJKvyOeW")
+                .unwrap()
+                .encoder,
+            Encoder::BCRYPTMD4
+        );
+    }
+
+    #[test]
+    fn parses_argon2() {
+        let info = HashInfo::parse("{argon2}$argon2id$v=19$m=4096,t=3,p=1$c29tZXNhbHQ$cGFzc3dvcmQ").unwrap();
+
+        assert_eq!(info.encoder, Encoder::ARGON2);
+        assert_eq!(
+            info.parameters,
+            HashParameters::Argon2 {
+                algorithm: String::from("argon2id"),
+                version: 0x13,
+                memory: 4096,
+                iterations: 3,
+                parallelism: 1
+            }
+        );
+        assert_eq!(info.salt, b"somesalt");
+        assert_eq!(info.digest_length, 8);
+        assert_eq!(info.encoding, HashEncoding::Base64);
+    }
+
+    #[test]
+    fn parses_scrypt() {
+        let info = HashInfo::parse("{scrypt}$100801$AAECAwQFBgcICQoLDA0ODw==$jWPkcxERY25E9gwism7ggXZkARLbUPyOZiOM5ZQx95s=").unwrap();
+
+        assert_eq!(
+            info.parameters,
+            HashParameters::SCrypt {
+                cpu_cost: 65536,
+                memory_cost: 8,
+                parallelization: 1
+            }
+        );
+        assert_eq!(info.salt, (0..16).collect::<Vec<u8>>());
+        assert_eq!(info.digest_length, 32);
+    }
+
+    #[test]
+    fn parses_pbkdf2_hex_and_base64() {
+        let info = HashInfo::parse("{pbkdf2}0102030405060708a6e20384a37755b292cf50dfb3b04397b78941542c59b0dd6ca69be36b465087").unwrap();
+
+        assert_eq!(info.parameters, HashParameters::Pbkdf2 { salt_length: Some(8) });
+        assert_eq!(info.salt, vec![1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(info.digest_length, 32);
+        assert_eq!(info.encoding, HashEncoding::Hex);
+
+        let info = HashInfo::parse("{pbkdf2}AQIDBAUGBwim4gOEo3dVspLPUN+zsEOXt4lBVCxZsN1sppvja0ZQhw==").unwrap();
+
+        assert_eq!(info.parameters, HashParameters::Pbkdf2 { salt_length: Some(8) });
+        assert_eq!(info.encoding, HashEncoding::Base64);
+    }
+
+    #[test]
+    fn parses_message_digest_salt_and_encoding() {
+        let info = HashInfo::parse("{MD5}{thisissalt}2a4e7104c2780098f50ed5a84bb2323d").unwrap();
+
+        assert_eq!(info.encoder, Encoder::MD5);
+        assert_eq!(info.salt, b"thisissalt");
+        assert_eq!(info.digest_length, 16);
+        assert_eq!(info.encoding, HashEncoding::Hex);
+
+        let info = HashInfo::parse("{MD5}{thisissalt}Kk5xBMJ4AJj1DtWoS7IyPQ==").unwrap();
+
+        assert_eq!(info.encoding, HashEncoding::Base64);
+
+        let info = HashInfo::parse("{SHA-1}ab2d02c9b8a9dcc678814c9270cdf932b6fb16c4").unwrap();
+
+        assert_eq!(info.encoder, Encoder::SHA1);
+        assert!(info.salt.is_empty());
+        assert_eq!(info.digest_length, 20);
+    }
+
+    #[test]
+    fn parses_standard() {
+        let info = HashInfo::parse("{sha256}010203040506070820750a715c83fd40289f5e08d0fa5fd4b02509ff0bc670c7f163ef9d5d24ad8d").unwrap();
+
+        assert_eq!(info.encoder, Encoder::STANDARD);
+        assert_eq!(info.salt, vec![1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(info.digest_length, 32);
+    }
+
+    #[test]
+    fn parses_ldap() {
+        let info = HashInfo::parse("{ldap}{SSHA}PQy2j+6n5ytA+YlAKkM8Fh4p6u2JxfVd").unwrap();
+
+        assert_eq!(info.salt.len(), 4);
+        assert_eq!(info.digest_length, 20);
+        assert_eq!(info.encoding, HashEncoding::Base64);
+
+        let info = HashInfo::parse("{ldap}boabspasswurd").unwrap();
+
+        assert_eq!(info.encoding, HashEncoding::Plain);
+        assert_eq!(
+            HashInfo::parse("{ldap}{MD5}ddSFGmjXYPbZC+NXR2kCzBRjqiE="),
+            Err(PasswordEncoderError::UnsupportedAlgorithm(String::from("{MD5}")))
+        );
+        assert_eq!(
+            HashInfo::parse("{ldap}{SsHa}PQy2j+6n5ytA+YlAKkM8Fh4p6u2JxfVd"),
+            Err(PasswordEncoderError::UnsupportedAlgorithm(String::from("{SsHa}")))
+        );
+        assert_eq!(HashInfo::parse("{ldap}{SSHAPQy2j+6n5ytA+YlAKkM8Fh4p6u2JxfVd"), Err(PasswordEncoderError::MalformedHash));
+    }
+
+    #[test]
+    fn parses_noop() {
+        let info = HashInfo::parse("{noop}password").unwrap();
+
+        assert_eq!(info.encoder, Encoder::NOOP);
+        assert_eq!(info.digest_length, 8);
+        assert_eq!(info.encoding, HashEncoding::Plain);
+    }
+
+    #[test]
+    fn error_for_missing_or_unmapped_id() {
+        assert_eq!(HashInfo::parse("password"), Err(PasswordEncoderError::MissingEncoderId));
+        assert_eq!(HashInfo::parse("{md5}password"), Err(PasswordEncoderError::UnmappedEncoderId(String::from("md5"))));
+        assert_eq!(
+            HashInfo::parse("{delegating}{noop}password"),
+            Err(PasswordEncoderError::UnmappedEncoderId(String::from("delegating")))
+        );
+    }
+
+    #[test]
+    fn parses_pbkdf2_hash_widths_of_encoder() {
+        // 8 bytes salt and 20 bytes hash
+        let legacy_encoder = Pbkdf2PasswordEncoder::new("", 8, 360000, 160);
+        let info = HashInfo::parse_for_pbkdf2(&legacy_encoder, "010203040506070839e6bbeadf98b4f9024a1184f61d4001aebba622").unwrap();
+
+        assert_eq!(info.parameters, HashParameters::Pbkdf2 { salt_length: Some(8) });
+        assert_eq!(info.salt, vec![1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(info.digest_length, 20);
+        assert_eq!(info.encoding, HashEncoding::Hex);
+
+        let info = HashInfo::parse_for_pbkdf2(
+            &Pbkdf2PasswordEncoder::default(),
+            "0102030405060708a6e20384a37755b292cf50dfb3b04397b78941542c59b0dd6ca69be36b465087",
+        )
+        .unwrap();
+
+        assert_eq!(info.parameters, HashParameters::Pbkdf2 { salt_length: Some(8) });
+        assert_eq!(info.digest_length, 32);
+
+        // 16 bytes salt and 64 bytes hash
+        let sha512_encoder = Pbkdf2PasswordEncoder::with_algorithm("secret", 16, 1000, SecretKeyFactoryAlgorithm::PBKDF2WithHmacSHA512);
+        let info = HashInfo::parse_for_pbkdf2(
+            &sha512_encoder,
+            "AAECAwQFBgcICQoLDA0OD+E0yWBvo5owWKvEm5K+T/GzxXoBUfVpKF5eadxls8ld/V05jAQ5vWS6siWHAGiZjKSIA+w52yrEroNrPxrKvOw=",
+        )
+        .unwrap();
+
+        assert_eq!(info.parameters, HashParameters::Pbkdf2 { salt_length: Some(16) });
+        assert_eq!(info.salt, (0..16).collect::<Vec<u8>>());
+        assert_eq!(info.digest_length, 64);
+        assert_eq!(info.encoding, HashEncoding::Base64);
+    }
+
+    #[test]
+    fn unknown_pbkdf2_split_for_other_hash_widths() {
+        let info = HashInfo::parse("{pbkdf2}010203040506070839e6bbeadf98b4f9024a1184f61d4001aebba622").unwrap();
+
+        assert_eq!(info.parameters, HashParameters::Pbkdf2 { salt_length: None });
+        assert!(info.salt.is_empty());
+        assert_eq!(info.digest_length, 28);
+        assert_eq!(info.encoding, HashEncoding::Hex);
+
+        let info = HashInfo::parse("{pbkdf2}AAECAwQFBgcICQoLDA0OD+E0yWBvo5owWKvEm5K+T/GzxXoBUfVpKF5eadxls8ld/V05jAQ5vWS6siWHAGiZjKSIA+w52yrEroNrPxrKvOw=").unwrap();
+
+        assert_eq!(info.parameters, HashParameters::Pbkdf2 { salt_length: None });
+        assert_eq!(info.digest_length, 80);
+    }
+
+    #[test]
+    fn parses_pbkdf2_base64_of_hex_characters_by_length() {
+        // 64 characters are the Base64 of the 48 bytes written by the encoder, as hex they would be 32 bytes
+        let info = HashInfo::parse("{pbkdf2@SpringSecurity_v5_8}0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef").unwrap();
+
+        assert_eq!(info.parameters, HashParameters::Pbkdf2 { salt_length: Some(16) });
+        assert_eq!(info.digest_length, 32);
+        assert_eq!(info.encoding, HashEncoding::Base64);
+    }
+
+    #[test]
+    fn error_for_malformed_hashes() {
+        assert_eq!(HashInfo::parse("{bcrypt}$2a$10$short"), Err(PasswordEncoderError::MalformedHash));
+        assert_eq!(
+            HashInfo::parse("{argon2}$argon2x$v=19$m=4096,t=3,p=1$c29tZXNhbHQ$cGFzc3dvcmQ"),
+            Err(PasswordEncoderError::MalformedHash)
+        );
+        assert_eq!(HashInfo::parse("{scrypt}$ffffffff$AAEC$AAEC"), Err(PasswordEncoderError::MalformedHash));
+        assert_eq!(HashInfo::parse("{pbkdf2}0102"), Err(PasswordEncoderError::MalformedHash));
+        assert_eq!(HashInfo::parse("{MD5}{thisissalt}2a4e71"), Err(PasswordEncoderError::MalformedHash));
+        assert_eq!(HashInfo::parse("{sha256}010203"), Err(PasswordEncoderError::MalformedHash));
+    }
+
+    #[test]
+    fn delegating_parses_nested_id() {
+        let info = HashInfo::parse_for(Encoder::DELEGATING, "{noop}password").unwrap();
+
+        assert_eq!(info.encoder, Encoder::NOOP);
+    }
+
+    #[test]
+    fn parses_spring_security_v5_8_ids() {
+        let info = HashInfo::parse("{pbkdf2@SpringSecurity_v5_8}0102030405060708090a0b0c0d0e0f10b94f951e97b99c4f592819f1d14004b125bf97431436c8043bc8faa95781b74a").unwrap();
+
+        assert_eq!(info.encoder, Encoder::PBKDF2V5_8);
+        assert_eq!(info.parameters, HashParameters::Pbkdf2 { salt_length: Some(16) });
+
+        let info = HashInfo::parse("{argon2@SpringSecurity_v5_8}$argon2id$v=19$m=16384,t=2,p=1$QUFBQUFBQUFBQUFBQUFBQQ$Pmbo2effrXPw4gygaMJwU4VdxCu7tjuARksTVxfFE9k").unwrap();
+
+        assert_eq!(info.encoder, Encoder::ARGON2V5_8);
+    }
+}
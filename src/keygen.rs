@@ -0,0 +1,53 @@
+use crate::codec::BASE64;
+use crate::error::PasswordEncoderError;
+use base64::Engine;
+use rand_core::{OsRng, RngCore};
+
+// https://github.com/spring-projects/spring-security/blob/0e3ee8990299436223c3037afb096f454f17ef5e/crypto/src/main/java/org/springframework/security/crypto/keygen/SecureRandomBytesKeyGenerator.java
+pub(crate) fn secure_random_bytes(key_length: usize) -> Result<Vec<u8>, PasswordEncoderError> {
+    let mut key = vec![0u8; key_length];
+    match OsRng.try_fill_bytes(&mut key) {
+        Ok(()) => Ok(key),
+        Err(_) => Err(PasswordEncoderError::RandomSourceFailure),
+    }
+}
+
+// https://github.com/spring-projects/spring-security/blob/a4e13c520b351c48378d0287167e53cfc581de46/crypto/src/main/java/org/springframework/security/crypto/keygen/Base64StringKeyGenerator.java
+pub(crate) fn base64_string_key(key_length: usize) -> Result<String, PasswordEncoderError> {
+    // spring security refuses to create shorter keys
+    if key_length < 32 {
+        return Err(PasswordEncoderError::InvalidParameters);
+    }
+    secure_random_bytes(key_length).map(|key| BASE64.encode(key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{base64_string_key, secure_random_bytes};
+    use crate::error::PasswordEncoderError;
+
+    #[test]
+    fn generates_wanted_length() {
+        assert_eq!(secure_random_bytes(16).unwrap().len(), 16);
+        assert_eq!(secure_random_bytes(0).unwrap().len(), 0);
+    }
+
+    #[test]
+    fn generates_different_keys() {
+        assert_ne!(secure_random_bytes(16).unwrap(), secure_random_bytes(16).unwrap());
+    }
+
+    #[test]
+    fn generates_base64_string_key() {
+        // 32 bytes need 44 chars including padding
+        let key = base64_string_key(32).unwrap();
+
+        assert_eq!(key.len(), 44);
+        assert!(key.ends_with('='));
+    }
+
+    #[test]
+    fn refuses_short_base64_string_key() {
+        assert_eq!(base64_string_key(31), Err(PasswordEncoderError::InvalidParameters));
+    }
+}
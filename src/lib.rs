@@ -1,22 +1,140 @@
 #![forbid(unsafe_code)]
+mod codec;
 pub mod encoder;
+pub mod error;
+pub mod factory;
+pub mod info;
+mod keygen;
+pub mod password;
+mod util;
+pub mod verification;
 
+use crate::error::PasswordEncoderError;
+use crate::password::SecretPassword;
+use crate::verification::Verification;
+use std::fmt;
+use std::str::FromStr;
+
+/// Encoders implementing only the infallible methods get the fallible ones for free, encoders hashing the raw bytes of
+/// a password should implement the byte based fallible methods and build the infallible methods on top of them
 pub trait PasswordEncoder {
-    fn matches_spring_security_hash(&self, unencoded_password: &String, encoded_password: &String) -> bool;
+    fn matches_spring_security_hash(&self, unencoded_password: &str, encoded_password: &str) -> bool;
 
     /// If password can be encoded, this might return the encoded password hash
-    fn encode_spring_security_hash(&self, unencoded_password: &String) -> Option<String>;
+    fn encode_spring_security_hash(&self, unencoded_password: &str) -> Option<String>;
+
+    /// Like `matches_spring_security_hash`, but only a wrong password results in `Ok(false)`,
+    /// an encoded password which can not be checked results in an error
+    fn try_matches_spring_security_hash(&self, unencoded_password: &str, encoded_password: &str) -> Result<bool, PasswordEncoderError> {
+        self.try_matches_spring_security_hash_bytes(unencoded_password.as_bytes(), encoded_password)
+    }
+
+    /// Like `encode_spring_security_hash`, but tells why the password could not be encoded
+    fn try_encode_spring_security_hash(&self, unencoded_password: &str) -> Result<String, PasswordEncoderError> {
+        self.try_encode_spring_security_hash_bytes(unencoded_password.as_bytes())
+    }
+
+    /// Like `try_matches_spring_security_hash` for passwords which are not necessarily UTF-8
+    fn try_matches_spring_security_hash_bytes(&self, unencoded_password: &[u8], encoded_password: &str) -> Result<bool, PasswordEncoderError> {
+        let unencoded_password = std::str::from_utf8(unencoded_password).map_err(|_| PasswordEncoderError::InvalidUtf8Password)?;
+        Ok(self.matches_spring_security_hash(unencoded_password, encoded_password))
+    }
+
+    /// Like `try_encode_spring_security_hash` for passwords which are not necessarily UTF-8
+    fn try_encode_spring_security_hash_bytes(&self, unencoded_password: &[u8]) -> Result<String, PasswordEncoderError> {
+        let unencoded_password = std::str::from_utf8(unencoded_password).map_err(|_| PasswordEncoderError::InvalidUtf8Password)?;
+        self.encode_spring_security_hash(unencoded_password).ok_or(PasswordEncoderError::EncodingFailed)
+    }
+
+    /// Whether the encoded password should be encoded again for better security, false unless overridden
+    fn upgrade_encoding(&self, encoded_password: &str) -> bool {
+        self.try_upgrade_encoding(encoded_password).unwrap_or(false)
+    }
+
+    /// Like `upgrade_encoding`, but an encoded password which can not be checked results in an error
+    fn try_upgrade_encoding(&self, _encoded_password: &str) -> Result<bool, PasswordEncoderError> {
+        Ok(false)
+    }
+
+    /// Matches the password and encodes it again when the encoded password should be upgraded
+    fn try_verify_and_upgrade_spring_security_hash(&self, unencoded_password: &str, encoded_password: &str) -> Result<Verification, PasswordEncoderError> {
+        self.try_verify_and_upgrade_spring_security_hash_bytes(unencoded_password.as_bytes(), encoded_password)
+    }
+
+    /// Like `try_verify_and_upgrade_spring_security_hash` for passwords which are not necessarily UTF-8
+    fn try_verify_and_upgrade_spring_security_hash_bytes(&self, unencoded_password: &[u8], encoded_password: &str) -> Result<Verification, PasswordEncoderError> {
+        if !self.try_matches_spring_security_hash_bytes(unencoded_password, encoded_password)? {
+            return Ok(Verification::Mismatch);
+        }
+        if !self.try_upgrade_encoding(encoded_password)? {
+            return Ok(Verification::Match);
+        }
+        Ok(Verification::Upgrade(self.try_encode_spring_security_hash_bytes(unencoded_password)?))
+    }
+}
+
+/// Shorter methods of `PasswordEncoder` accepting anything like `String`, `&str` or `Cow<str>`,
+/// kept apart to leave `PasswordEncoder` usable as trait object
+pub trait PasswordEncoderExt: PasswordEncoder {
+    fn matches(&self, unencoded_password: impl AsRef<str>, encoded_password: impl AsRef<str>) -> bool {
+        self.matches_spring_security_hash(unencoded_password.as_ref(), encoded_password.as_ref())
+    }
+
+    fn encode(&self, unencoded_password: impl AsRef<str>) -> Option<String> {
+        self.encode_spring_security_hash(unencoded_password.as_ref())
+    }
+
+    fn try_matches(&self, unencoded_password: impl AsRef<str>, encoded_password: impl AsRef<str>) -> Result<bool, PasswordEncoderError> {
+        self.try_matches_spring_security_hash(unencoded_password.as_ref(), encoded_password.as_ref())
+    }
+
+    fn try_encode(&self, unencoded_password: impl AsRef<str>) -> Result<String, PasswordEncoderError> {
+        self.try_encode_spring_security_hash(unencoded_password.as_ref())
+    }
+
+    fn matches_secret(&self, unencoded_password: &SecretPassword, encoded_password: impl AsRef<str>) -> bool {
+        self.try_matches_secret(unencoded_password, encoded_password).unwrap_or(false)
+    }
+
+    fn encode_secret(&self, unencoded_password: &SecretPassword) -> Option<String> {
+        self.try_encode_secret(unencoded_password).ok()
+    }
+
+    fn try_matches_secret(&self, unencoded_password: &SecretPassword, encoded_password: impl AsRef<str>) -> Result<bool, PasswordEncoderError> {
+        self.try_matches_spring_security_hash_bytes(unencoded_password.as_bytes(), encoded_password.as_ref())
+    }
+
+    fn try_encode_secret(&self, unencoded_password: &SecretPassword) -> Result<String, PasswordEncoderError> {
+        self.try_encode_spring_security_hash_bytes(unencoded_password.as_bytes())
+    }
+
+    fn try_verify_and_upgrade(&self, unencoded_password: impl AsRef<str>, encoded_password: impl AsRef<str>) -> Result<Verification, PasswordEncoderError> {
+        self.try_verify_and_upgrade_spring_security_hash(unencoded_password.as_ref(), encoded_password.as_ref())
+    }
+
+    fn try_verify_and_upgrade_secret(&self, unencoded_password: &SecretPassword, encoded_password: impl AsRef<str>) -> Result<Verification, PasswordEncoderError> {
+        self.try_verify_and_upgrade_spring_security_hash_bytes(unencoded_password.as_bytes(), encoded_password.as_ref())
+    }
 }
 
+impl<T: PasswordEncoder + ?Sized> PasswordEncoderExt for T {}
+
+/// Algorithms of the encoders, displayed and parsed by the ids of the Spring Security `PasswordEncoderFactories`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Encoder {
     ARGON2,
+    ARGON2V5_8,
     BCRYPT,
+    BCRYPTMD4,
+    BCRYPTMD5,
     LDAP,
     MD4,
     MD5,
     NOOP,
     PBKDF2,
+    PBKDF2V5_8,
     SCRYPT,
+    SCRYPTV5_8,
     SHA1,
     SHA256,
     STANDARD,
@@ -24,15 +142,187 @@ pub enum Encoder {
 }
 
 impl Encoder {
-    fn to_string(&self) -> String {
+    pub const ALL: [Encoder; 17] = [
+        Encoder::ARGON2,
+        Encoder::ARGON2V5_8,
+        Encoder::BCRYPT,
+        Encoder::BCRYPTMD4,
+        Encoder::BCRYPTMD5,
+        Encoder::LDAP,
+        Encoder::MD4,
+        Encoder::MD5,
+        Encoder::NOOP,
+        Encoder::PBKDF2,
+        Encoder::PBKDF2V5_8,
+        Encoder::SCRYPT,
+        Encoder::SCRYPTV5_8,
+        Encoder::SHA1,
+        Encoder::SHA256,
+        Encoder::STANDARD,
+        Encoder::DELEGATING,
+    ];
+
+    /// The id used in front of hashes of the delegating encoder, like `SHA-256` in `{SHA-256}...`
+    pub fn id(&self) -> &'static str {
         // https://github.com/spring-projects/spring-security/blob/0e3ee8990299436223c3037afb096f454f17ef5e/crypto/src/main/java/org/springframework/security/crypto/factory/PasswordEncoderFactories.java#L72
         match self {
-            Encoder::ARGON2 => "argon2".to_string(),
-            Encoder::BCRYPT => "bcrypt".to_string(),
-            Encoder::LDAP => "ldap".to_string(),
-            Encoder::MD4 => "MD4".to_string(),
-            Encoder::MD5 => "MD5".to_string(),
-            _ => "".to_string(),
+            Encoder::ARGON2 => "argon2",
+            Encoder::ARGON2V5_8 => "argon2@SpringSecurity_v5_8",
+            Encoder::BCRYPT => "bcrypt",
+            // not mapped by spring security, legacy digests wrapped by bcrypt
+            Encoder::BCRYPTMD4 => "bcrypt-md4",
+            Encoder::BCRYPTMD5 => "bcrypt-md5",
+            Encoder::LDAP => "ldap",
+            Encoder::MD4 => "MD4",
+            Encoder::MD5 => "MD5",
+            Encoder::NOOP => "noop",
+            Encoder::PBKDF2 => "pbkdf2",
+            Encoder::PBKDF2V5_8 => "pbkdf2@SpringSecurity_v5_8",
+            Encoder::SCRYPT => "scrypt",
+            Encoder::SCRYPTV5_8 => "scrypt@SpringSecurity_v5_8",
+            Encoder::SHA1 => "SHA-1",
+            Encoder::SHA256 => "SHA-256",
+            Encoder::STANDARD => "sha256",
+            // not mapped by spring security, the delegating encoder is the factory itself
+            Encoder::DELEGATING => "delegating",
         }
     }
 }
+
+impl fmt::Display for Encoder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.id())
+    }
+}
+
+impl FromStr for Encoder {
+    type Err = PasswordEncoderError;
+
+    /// Parses the exact ids only, use `EncoderAliases` for case-insensitive names
+    fn from_str(id: &str) -> Result<Encoder, PasswordEncoderError> {
+        Encoder::ALL
+            .iter()
+            .find(|encoder| encoder.id() == id)
+            .copied()
+            .ok_or_else(|| PasswordEncoderError::UnmappedEncoderId(id.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Encoder, PasswordEncoder, PasswordEncoderError, PasswordEncoderExt, SecretPassword, Verification};
+    use std::borrow::Cow;
+
+    // only implements the infallible api
+    struct NeverEncodingPasswordEncoder;
+
+    impl PasswordEncoder for NeverEncodingPasswordEncoder {
+        fn matches_spring_security_hash(&self, unencoded_password: &str, encoded_password: &str) -> bool {
+            unencoded_password == encoded_password
+        }
+
+        fn encode_spring_security_hash(&self, _unencoded_password: &str) -> Option<String> {
+            None
+        }
+    }
+
+    #[test]
+    fn fallible_api_defaults_to_infallible_api() {
+        let encoder = NeverEncodingPasswordEncoder;
+
+        assert_eq!(encoder.try_matches_spring_security_hash("password", "password"), Ok(true));
+        assert_eq!(encoder.try_matches_spring_security_hash("password", "other"), Ok(false));
+        assert_eq!(encoder.try_encode_spring_security_hash("password"), Err(PasswordEncoderError::EncodingFailed));
+    }
+
+    #[test]
+    fn byte_api_defaults_to_infallible_api_for_utf8() {
+        let encoder = NeverEncodingPasswordEncoder;
+
+        assert_eq!(encoder.try_matches_spring_security_hash_bytes(b"password", "password"), Ok(true));
+        assert_eq!(
+            encoder.try_matches_spring_security_hash_bytes(b"\xff", "password"),
+            Err(PasswordEncoderError::InvalidUtf8Password)
+        );
+        assert_eq!(encoder.try_encode_spring_security_hash_bytes(b"\xff"), Err(PasswordEncoderError::InvalidUtf8Password));
+    }
+
+    #[test]
+    fn ext_accepts_any_string_type() {
+        let encoder = NeverEncodingPasswordEncoder;
+
+        assert!(encoder.matches("password", String::from("password")));
+        assert!(encoder.matches(Cow::Borrowed("password"), "password"));
+        assert_eq!(encoder.try_matches(String::from("password"), "other"), Ok(false));
+        assert_eq!(encoder.encode("password"), None);
+    }
+
+    #[test]
+    fn ext_accepts_secret_password() {
+        let encoder = NeverEncodingPasswordEncoder;
+
+        assert!(encoder.matches_secret(&SecretPassword::from("password"), "password"));
+        assert_eq!(
+            encoder.try_matches_secret(&SecretPassword::from(vec![0xff]), "password"),
+            Err(PasswordEncoderError::InvalidUtf8Password)
+        );
+        assert_eq!(encoder.encode_secret(&SecretPassword::from("password")), None);
+    }
+
+    #[test]
+    fn ext_works_on_trait_objects() {
+        let encoder: Box<dyn PasswordEncoder> = Box::new(NeverEncodingPasswordEncoder);
+
+        assert!(encoder.matches("password", "password"));
+    }
+
+    #[test]
+    fn displays_spring_security_ids() {
+        assert_eq!(Encoder::SHA1.to_string(), "SHA-1");
+        assert_eq!(Encoder::SHA256.to_string(), "SHA-256");
+        assert_eq!(Encoder::STANDARD.to_string(), "sha256");
+        assert_eq!(Encoder::NOOP.to_string(), "noop");
+        assert_eq!(Encoder::PBKDF2.to_string(), "pbkdf2");
+    }
+
+    #[test]
+    fn parses_displayed_ids() {
+        for encoder in Encoder::ALL {
+            assert_eq!(encoder.to_string().parse::<Encoder>(), Ok(encoder));
+        }
+    }
+
+    #[test]
+    fn parses_ids_case_sensitive() {
+        assert_eq!("sha256".parse::<Encoder>(), Ok(Encoder::STANDARD));
+        assert_eq!("md5".parse::<Encoder>(), Err(PasswordEncoderError::UnmappedEncoderId(String::from("md5"))));
+        assert_eq!("".parse::<Encoder>(), Err(PasswordEncoderError::UnmappedEncoderId(String::new())));
+    }
+
+    #[test]
+    fn no_upgrade_encoding_by_default() {
+        let encoder = NeverEncodingPasswordEncoder;
+
+        assert!(!encoder.upgrade_encoding("password"));
+        assert_eq!(encoder.try_upgrade_encoding("password"), Ok(false));
+    }
+
+    #[test]
+    fn verify_and_upgrade_without_upgrade() {
+        let encoder = NeverEncodingPasswordEncoder;
+
+        assert_eq!(encoder.try_verify_and_upgrade("password", "password"), Ok(Verification::Match));
+        assert_eq!(encoder.try_verify_and_upgrade("password", "other"), Ok(Verification::Mismatch));
+        assert_eq!(
+            encoder.try_verify_and_upgrade_secret(&SecretPassword::from("password"), "password"),
+            Ok(Verification::Match)
+        );
+    }
+
+    #[test]
+    fn displays_spring_security_v5_8_ids() {
+        assert_eq!(Encoder::PBKDF2V5_8.to_string(), "pbkdf2@SpringSecurity_v5_8");
+        assert_eq!(Encoder::SCRYPTV5_8.to_string(), "scrypt@SpringSecurity_v5_8");
+        assert_eq!(Encoder::ARGON2V5_8.to_string(), "argon2@SpringSecurity_v5_8");
+    }
+}
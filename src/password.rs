@@ -0,0 +1,103 @@
+use crate::util::constant_time_equals;
+use std::fmt;
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+/// Plaintext password which gets wiped from memory when dropped and never shows up in debug output
+#[derive(Clone, Default)]
+pub struct SecretPassword(Zeroizing<Vec<u8>>);
+
+impl SecretPassword {
+    /// Takes over the given buffer, so a `String` or `Vec<u8>` is not copied
+    pub fn new(password: impl Into<Vec<u8>>) -> SecretPassword {
+        SecretPassword(Zeroizing::new(password.into()))
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Only passwords which are valid UTF-8 can be seen as string
+    pub fn as_str(&self) -> Option<&str> {
+        std::str::from_utf8(&self.0).ok()
+    }
+}
+
+impl From<String> for SecretPassword {
+    fn from(password: String) -> SecretPassword {
+        SecretPassword::new(password)
+    }
+}
+
+impl From<&str> for SecretPassword {
+    fn from(password: &str) -> SecretPassword {
+        SecretPassword::new(password)
+    }
+}
+
+impl From<Vec<u8>> for SecretPassword {
+    fn from(password: Vec<u8>) -> SecretPassword {
+        SecretPassword::new(password)
+    }
+}
+
+// a derived comparison would stop at the first differing byte
+impl PartialEq for SecretPassword {
+    fn eq(&self, other: &SecretPassword) -> bool {
+        constant_time_equals(self.as_bytes(), other.as_bytes())
+    }
+}
+
+impl Eq for SecretPassword {}
+
+impl fmt::Debug for SecretPassword {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SecretPassword(***)")
+    }
+}
+
+impl Zeroize for SecretPassword {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for SecretPassword {}
+
+#[cfg(test)]
+mod tests {
+    use super::SecretPassword;
+    use zeroize::Zeroize;
+
+    #[test]
+    fn redacts_debug_output() {
+        let password = SecretPassword::from("password");
+
+        assert_eq!(format!("{:?}", password), "SecretPassword(***)");
+        assert_eq!(format!("{:?}", Some(password)), "Some(SecretPassword(***))");
+    }
+
+    #[test]
+    fn keeps_bytes() {
+        let password = SecretPassword::from(vec![0xff, b'a']);
+
+        assert_eq!(password.as_bytes(), &[0xff, b'a']);
+        assert_eq!(password.as_str(), None);
+        assert_eq!(SecretPassword::from(String::from("password")).as_str(), Some("password"));
+    }
+
+    #[test]
+    fn compares_bytes() {
+        assert_eq!(SecretPassword::from("password"), SecretPassword::from(b"password".to_vec()));
+        assert_ne!(SecretPassword::from("password"), SecretPassword::from("passwort"));
+        assert_ne!(SecretPassword::from("password"), SecretPassword::from("password1"));
+    }
+
+    #[test]
+    fn zeroize_clears_password() {
+        let mut password = SecretPassword::from("password");
+
+        password.zeroize();
+
+        assert!(password.as_bytes().is_empty());
+    }
+}
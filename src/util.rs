@@ -0,0 +1,30 @@
+use subtle::ConstantTimeEq;
+
+// like MessageDigest.isEqual, only the length may leak as it is no secret for any hash format
+// https://github.com/spring-projects/spring-security/blob/0e3ee8990299436223c3037afb096f454f17ef5e/crypto/src/main/java/org/springframework/security/crypto/password/PasswordEncoderUtils.java
+pub(crate) fn constant_time_equals(expected: &[u8], actual: &[u8]) -> bool {
+    expected.ct_eq(actual).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::constant_time_equals;
+
+    #[test]
+    fn equal_arrays() {
+        assert!(constant_time_equals(b"password", b"password"));
+        assert!(constant_time_equals(b"", b""));
+    }
+
+    #[test]
+    fn different_arrays() {
+        assert!(!constant_time_equals(b"password", b"passwort"));
+        assert!(!constant_time_equals(b"password", b"Password"));
+    }
+
+    #[test]
+    fn different_lengths() {
+        assert!(!constant_time_equals(b"password", b"password1"));
+        assert!(!constant_time_equals(b"password", b""));
+    }
+}
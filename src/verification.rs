@@ -0,0 +1,42 @@
+/// Outcome of matching a password, telling whether the encoded password should be replaced
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Verification {
+    /// The password does not match the encoded password
+    Mismatch,
+    /// The password matches and the encoded password is up to date
+    Match,
+    /// The password matches, the encoded password should be replaced by the contained one
+    Upgrade(String),
+}
+
+impl Verification {
+    pub fn matches(&self) -> bool {
+        !matches!(self, Verification::Mismatch)
+    }
+
+    /// The new encoded password to store, if any
+    pub fn upgraded_encoded_password(&self) -> Option<&str> {
+        match self {
+            Verification::Upgrade(encoded_password) => Some(encoded_password),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Verification;
+
+    #[test]
+    fn only_mismatch_does_not_match() {
+        assert!(!Verification::Mismatch.matches());
+        assert!(Verification::Match.matches());
+        assert!(Verification::Upgrade(String::from("{noop}password")).matches());
+    }
+
+    #[test]
+    fn only_upgrade_has_new_encoded_password() {
+        assert_eq!(Verification::Match.upgraded_encoded_password(), None);
+        assert_eq!(Verification::Upgrade(String::from("{noop}password")).upgraded_encoded_password(), Some("{noop}password"));
+    }
+}
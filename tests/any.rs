@@ -0,0 +1,20 @@
+use password_encoder_for_spring_security_hashes::encoder::any::AnyEncoder;
+use password_encoder_for_spring_security_hashes::encoder::sha256::Sha256PasswordEncoder;
+use password_encoder_for_spring_security_hashes::{Encoder, PasswordEncoder};
+
+#[test]
+fn crate_any_encode_configured() {
+    let given_password = String::from("Hello");
+
+    let encoders = [
+        AnyEncoder::from(Encoder::MD5),
+        AnyEncoder::from(Sha256PasswordEncoder::default().with_iterations(3).unwrap()),
+    ];
+
+    for encoder in encoders {
+        let encoded_password = encoder.encode_spring_security_hash(&given_password).unwrap();
+
+        assert_ne!(encoded_password, given_password);
+        assert!(encoder.matches_spring_security_hash(&given_password, &encoded_password));
+    }
+}
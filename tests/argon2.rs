@@ -0,0 +1,15 @@
+use password_encoder_for_spring_security_hashes::encoder::argon2::Argon2PasswordEncoder;
+use password_encoder_for_spring_security_hashes::PasswordEncoder;
+
+#[test]
+fn crate_argon2_encode_default() {
+    let given_password = String::from("Hello");
+
+    let encoder: Argon2PasswordEncoder = Default::default();
+
+    let encoded_password = encoder.encode_spring_security_hash(&given_password).unwrap();
+
+    assert_ne!(encoded_password, given_password);
+
+    assert!(encoder.matches_spring_security_hash(&given_password, &encoded_password));
+}
@@ -0,0 +1,28 @@
+use password_encoder_for_spring_security_hashes::encoder::bcrypt::{BCryptPasswordEncoder, BCryptVersion};
+use password_encoder_for_spring_security_hashes::PasswordEncoder;
+
+#[test]
+fn crate_bcrypt_encode_default() {
+    let given_password = String::from("Hello");
+
+    let encoder: BCryptPasswordEncoder = Default::default();
+
+    let encoded_password = encoder.encode_spring_security_hash(&given_password).unwrap();
+
+    assert_ne!(encoded_password, given_password);
+
+    assert!(encoder.matches_spring_security_hash(&given_password, &encoded_password));
+}
+
+#[test]
+fn crate_bcrypt_encode_with_version_and_strength() {
+    let given_password = String::from("Hello");
+
+    let encoder = BCryptPasswordEncoder::new(BCryptVersion::TwoB, 12).unwrap();
+
+    let encoded_password = encoder.encode_spring_security_hash(&given_password).unwrap();
+
+    assert!(encoded_password.starts_with("$2b$12$"));
+
+    assert!(encoder.matches_spring_security_hash(&given_password, &encoded_password));
+}
@@ -0,0 +1,207 @@
+// scans the matching code of all encoders, as comparing secret derived data with == stops at the first difference
+use std::ffi::OsStr;
+use std::fs;
+use std::path::Path;
+
+const MATCHING_FUNCTION: &str = "fn try_matches_spring_security_hash_bytes";
+const SHORT_CIRCUITING_COMPARISONS: [&str; 4] = ["==", "!=", ".eq(", ".ne("];
+// the delegating encoders only pass on to other encoders
+const CONSTANT_TIME_COMPARISONS: [&str; 2] = ["constant_time_equals(", ".try_matches_spring_security_hash_bytes("];
+// comparisons outside the matching functions must not compare byte slices or secret derived values
+const SLICE_OPERANDS: [&str; 6] = ["as_bytes()", "as_slice()", "as_ref()", "to_vec()", "[..", "&["];
+const SECRET_NAME_SUFFIXES: [&str; 4] = ["digest", "hash", "derived", "key"];
+
+fn encoder_sources() -> Vec<(String, String)> {
+    let encoder_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("src").join("encoder");
+    let mut sources: Vec<(String, String)> = fs::read_dir(encoder_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension() == Some(OsStr::new("rs")))
+        .map(|path| (path.file_name().unwrap().to_string_lossy().to_string(), fs::read_to_string(&path).unwrap()))
+        .collect();
+    sources.sort();
+    sources
+}
+
+// removes comments and string literals, so only code is left
+fn strip_comments_and_strings(source: &str) -> String {
+    let mut code = String::new();
+    for line in source.lines() {
+        let mut in_string = false;
+        let mut previous = ' ';
+        for (position, character) in line.char_indices() {
+            if !in_string && line[position..].starts_with("//") {
+                break;
+            }
+            if character == '"' && previous != '\\' {
+                in_string = !in_string;
+            } else if !in_string {
+                code.push(character);
+            }
+            previous = character;
+        }
+        code.push('\n');
+    }
+    code
+}
+
+// position of the brace closing the first block after the start
+fn block_end(code: &str, start: usize) -> usize {
+    let block_start = start + code[start..].find('{').unwrap();
+    let mut depth = 0;
+    for (position, character) in code[block_start..].char_indices() {
+        match character {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            _ => {}
+        }
+        if depth == 0 {
+            return block_start + position;
+        }
+    }
+    code.len() - 1
+}
+
+fn function_bodies<'a>(code: &'a str, function: &str) -> Vec<&'a str> {
+    let mut bodies = vec![];
+    let mut rest = code;
+    while let Some(start) = rest.find(function) {
+        let body_start = start + rest[start..].find('{').unwrap();
+        let body_end = block_end(rest, start);
+        bodies.push(&rest[body_start..=body_end]);
+        rest = &rest[body_end..];
+    }
+    bodies
+}
+
+fn without_test_modules(code: &str) -> String {
+    let mut code = code.to_string();
+    while let Some(start) = code.find("#[cfg(test)]") {
+        let end = block_end(&code, start);
+        code.replace_range(start..=end, "");
+    }
+    code
+}
+
+// identifiers of a line with the text following each of them
+fn identifiers(line: &str) -> Vec<(&str, &str)> {
+    let mut identifiers = vec![];
+    let mut start = None;
+    for (position, character) in line.char_indices().chain(Some((line.len(), ' '))) {
+        match (start, character.is_alphanumeric() || character == '_') {
+            (None, true) => start = Some(position),
+            (Some(identifier_start), false) => {
+                identifiers.push((&line[identifier_start..position], &line[position..]));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    identifiers
+}
+
+// lengths of secret derived values may be compared, their contents may not
+fn compares_secret(line: &str) -> bool {
+    SLICE_OPERANDS.iter().any(|operand| line.contains(operand))
+        || identifiers(line)
+            .into_iter()
+            .any(|(identifier, following)| SECRET_NAME_SUFFIXES.iter().any(|suffix| identifier.ends_with(suffix)) && !following.starts_with(".len()"))
+}
+
+fn secret_comparisons(code: &str) -> Vec<String> {
+    without_test_modules(code)
+        .lines()
+        .filter(|line| SHORT_CIRCUITING_COMPARISONS.iter().any(|comparison| line.contains(comparison)))
+        .filter(|line| compares_secret(line))
+        .map(|line| line.trim().to_string())
+        .collect()
+}
+
+#[test]
+fn every_encoder_implements_byte_matching() {
+    for (file_name, source) in encoder_sources() {
+        let code = strip_comments_and_strings(&source);
+        if code.contains("PasswordEncoder for") {
+            assert!(code.contains(MATCHING_FUNCTION), "{} should implement {}", file_name, MATCHING_FUNCTION);
+        }
+    }
+}
+
+#[test]
+fn matching_does_not_short_circuit() {
+    for (file_name, source) in encoder_sources() {
+        let code = strip_comments_and_strings(&source);
+        for body in function_bodies(&code, MATCHING_FUNCTION) {
+            for comparison in SHORT_CIRCUITING_COMPARISONS {
+                assert!(!body.contains(comparison), "{} compares with {} while matching", file_name, comparison);
+            }
+            assert!(
+                CONSTANT_TIME_COMPARISONS.iter().any(|comparison| body.contains(comparison)),
+                "{} should compare in constant time while matching",
+                file_name
+            );
+        }
+    }
+}
+
+#[test]
+fn helpers_do_not_short_circuit() {
+    for (file_name, source) in encoder_sources() {
+        let code = strip_comments_and_strings(&source);
+        assert_eq!(
+            secret_comparisons(&code),
+            Vec::<String>::new(),
+            "{} compares secret derived data with a short circuiting comparison",
+            file_name
+        );
+    }
+}
+
+#[test]
+fn harness_finds_short_circuiting_comparison() {
+    let code = strip_comments_and_strings(
+        r#"
+        fn try_matches_spring_security_hash_bytes(&self) -> Result<bool, PasswordEncoderError> {
+            // a == b in a comment is fine
+            let message = "and in a string == too";
+            if true { Ok(digest == stored) } else { Ok(false) }
+        }
+        "#,
+    );
+
+    let bodies = function_bodies(&code, MATCHING_FUNCTION);
+
+    assert_eq!(bodies.len(), 1);
+    assert_eq!(bodies[0].matches("==").count(), 1);
+}
+
+#[test]
+fn harness_finds_short_circuiting_comparison_in_helpers() {
+    let code = strip_comments_and_strings(
+        r#"
+        fn compare(digest: &[u8], stored: &[u8]) -> bool {
+            digest.len() == stored.len() && digest == stored
+        }
+
+        fn compare_slices(&self, stored: &str) -> bool {
+            self.encode().as_bytes() != stored.as_bytes()
+        }
+
+        fn lengths(salt_and_hash: &str, parts: &[&str]) -> bool {
+            salt_and_hash.len() == 53 && parts.len() != 4
+        }
+
+        #[cfg(test)]
+        mod tests {
+            fn compare(digest: &[u8]) -> bool {
+                digest == b"test"
+            }
+        }
+        "#,
+    );
+
+    assert_eq!(
+        secret_comparisons(&code),
+        vec!["digest.len() == stored.len() && digest == stored", "self.encode().as_bytes() != stored.as_bytes()"]
+    );
+}
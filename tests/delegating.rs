@@ -1,5 +1,13 @@
+use password_encoder_for_spring_security_hashes::encoder::bcrypt::{BCryptPasswordEncoder, BCryptVersion};
 use password_encoder_for_spring_security_hashes::encoder::delegating::DelegatingPasswordEncoder;
-use password_encoder_for_spring_security_hashes::PasswordEncoder;
+use password_encoder_for_spring_security_hashes::encoder::md5::Md5PasswordEncoder;
+use password_encoder_for_spring_security_hashes::encoder::onion::BCryptMd5PasswordEncoder;
+use password_encoder_for_spring_security_hashes::error::PasswordEncoderError;
+use password_encoder_for_spring_security_hashes::password::SecretPassword;
+use password_encoder_for_spring_security_hashes::verification::Verification;
+use password_encoder_for_spring_security_hashes::{PasswordEncoder, PasswordEncoderExt};
+use std::sync::Arc;
+use std::thread;
 
 #[test]
 fn crate_delegating_encode_default() {
@@ -16,3 +24,216 @@ fn crate_delegating_encode_default() {
 
     assert!(encoder.matches_spring_security_hash(&given_password, &encoded_password));
 }
+
+#[test]
+fn crate_delegating_matches_argon2() {
+    let given_password = String::from("sometestpassword");
+    let stored_password = String::from("{argon2}$argon2id$v=19$m=4096,t=3,p=1$QUFBQUFBQUFBQUFBQUFBQQ$hmmTNyJlwbb6HAvFoHFWF+u03fdb0F2qA+39oPlcAqo");
+
+    let encoder: DelegatingPasswordEncoder = Default::default();
+
+    assert!(encoder.matches_spring_security_hash(&given_password, &stored_password));
+}
+
+#[test]
+fn crate_delegating_matches_pbkdf2() {
+    let given_password = String::from("password");
+    let stored_password = String::from("{pbkdf2}0102030405060708a6e20384a37755b292cf50dfb3b04397b78941542c59b0dd6ca69be36b465087");
+
+    let encoder: DelegatingPasswordEncoder = Default::default();
+
+    assert!(encoder.matches_spring_security_hash(&given_password, &stored_password));
+}
+
+#[test]
+fn crate_delegating_matches_scrypt() {
+    let given_password = String::from("password");
+    let stored_password =
+        String::from("{scrypt}$e0801$AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8gISIjJCUmJygpKissLS4vMDEyMzQ1Njc4OTo7PD0+Pw==$SNjiD+Mi6ArATkQKmDWdB+boUwy1pEiiq4yCoxxJoVM=");
+
+    let encoder: DelegatingPasswordEncoder = Default::default();
+
+    assert!(encoder.matches_spring_security_hash(&given_password, &stored_password));
+}
+
+#[test]
+fn crate_delegating_matches_ldap() {
+    let given_password = String::from("boabspasswurd");
+    let stored_password = String::from("{ldap}{SSHA}PQy2j+6n5ytA+YlAKkM8Fh4p6u2JxfVd");
+
+    let encoder: DelegatingPasswordEncoder = Default::default();
+
+    assert!(encoder.matches_spring_security_hash(&given_password, &stored_password));
+}
+
+#[test]
+fn crate_delegating_matches_sha1() {
+    let given_password = String::from("password");
+    let stored_password = String::from("{SHA-1}{thisissalt}ab2d02c9b8a9dcc678814c9270cdf932b6fb16c4");
+
+    let encoder: DelegatingPasswordEncoder = Default::default();
+
+    assert!(encoder.matches_spring_security_hash(&given_password, &stored_password));
+}
+
+#[test]
+fn crate_delegating_matches_sha256() {
+    let given_password = String::from("password");
+    let stored_password = String::from("{SHA-256}{thisissalt}3ffac017def094e1cd0dba405566ad658b3e6b2585b9b814fd35da47ae528262");
+
+    let encoder: DelegatingPasswordEncoder = Default::default();
+
+    assert!(encoder.matches_spring_security_hash(&given_password, &stored_password));
+}
+
+#[test]
+fn crate_delegating_matches_standard() {
+    let given_password = String::from("password");
+    let stored_password = String::from("{sha256}010203040506070820750a715c83fd40289f5e08d0fa5fd4b02509ff0bc670c7f163ef9d5d24ad8d");
+
+    let encoder: DelegatingPasswordEncoder = Default::default();
+
+    assert!(encoder.matches_spring_security_hash(&given_password, &stored_password));
+}
+
+#[test]
+fn crate_delegating_with_registered_encoder_shared_between_threads() {
+    let given_password = String::from("Hello");
+
+    let mut encoder: DelegatingPasswordEncoder = Default::default();
+    encoder.register_encoder("bcrypt", BCryptPasswordEncoder::new(BCryptVersion::TwoB, 4).unwrap());
+    let encoder = Arc::new(encoder);
+
+    let encoded_password = {
+        let encoder = Arc::clone(&encoder);
+        let given_password = given_password.clone();
+        thread::spawn(move || encoder.encode_spring_security_hash(&given_password).unwrap()).join().unwrap()
+    };
+
+    assert!(encoded_password.starts_with("{bcrypt}$2b$04$"));
+
+    assert!(encoder.matches_spring_security_hash(&given_password, &encoded_password));
+}
+
+#[test]
+fn crate_delegating_matches_legacy_hash_without_id() {
+    let given_password = String::from("password");
+    let stored_password = String::from("{thisissalt}2a4e7104c2780098f50ed5a84bb2323d");
+
+    let mut encoder: DelegatingPasswordEncoder = Default::default();
+    assert!(!encoder.matches_spring_security_hash(&given_password, &stored_password));
+
+    encoder.set_default_password_encoder_for_matches(Md5PasswordEncoder::default());
+    assert!(encoder.matches_spring_security_hash(&given_password, &stored_password));
+}
+
+#[test]
+fn crate_delegating_reports_unmapped_id() {
+    let encoder: DelegatingPasswordEncoder = Default::default();
+
+    let result = encoder.try_matches_spring_security_hash("password", "{foo}password");
+
+    assert_eq!(result, Err(PasswordEncoderError::UnmappedEncoderId(String::from("foo"))));
+    assert!(!encoder.matches_spring_security_hash(&String::from("password"), &String::from("{foo}password")));
+}
+
+#[test]
+fn crate_delegating_accepts_str_and_bytes() {
+    let encoder: DelegatingPasswordEncoder = Default::default();
+
+    let encoded_password = encoder.encode("Hello").unwrap();
+
+    assert!(encoder.matches("Hello", &encoded_password));
+    assert!(encoder.matches_spring_security_hash("Hello", &encoded_password));
+    assert_eq!(encoder.try_matches_spring_security_hash_bytes(b"Hello", &encoded_password), Ok(true));
+    assert_eq!(encoder.try_matches_spring_security_hash_bytes(b"\xffHello", &encoded_password), Ok(false));
+}
+
+#[test]
+fn crate_delegating_accepts_secret_password() {
+    let encoder: DelegatingPasswordEncoder = Default::default();
+
+    let password = SecretPassword::from(String::from("Hello"));
+    let encoded_password = encoder.encode_secret(&password).unwrap();
+
+    assert!(encoder.matches_secret(&password, &encoded_password));
+    assert!(!encoder.matches_secret(&SecretPassword::from("World"), &encoded_password));
+}
+
+#[test]
+fn crate_delegating_migrates_legacy_md5_on_login() {
+    let encoder: DelegatingPasswordEncoder = Default::default();
+
+    let mut stored_password = String::from("{MD5}{thisissalt}2a4e7104c2780098f50ed5a84bb2323d");
+
+    assert_eq!(encoder.try_verify_and_upgrade("wrongpassword", &stored_password), Ok(Verification::Mismatch));
+
+    if let Ok(Verification::Upgrade(upgraded_password)) = encoder.try_verify_and_upgrade("password", &stored_password) {
+        stored_password = upgraded_password;
+    }
+
+    assert!(stored_password.starts_with("{bcrypt}"));
+    assert_eq!(encoder.try_verify_and_upgrade("password", &stored_password), Ok(Verification::Match));
+}
+
+#[test]
+fn crate_delegating_matches_spring_security_v5_8_fixtures() {
+    let encoder: DelegatingPasswordEncoder = Default::default();
+
+    let stored_passwords = [
+        "{pbkdf2@SpringSecurity_v5_8}0102030405060708090a0b0c0d0e0f10b94f951e97b99c4f592819f1d14004b125bf97431436c8043bc8faa95781b74a",
+        "{scrypt@SpringSecurity_v5_8}$100801$AAECAwQFBgcICQoLDA0ODw==$jWPkcxERY25E9gwism7ggXZkARLbUPyOZiOM5ZQx95s=",
+        "{argon2@SpringSecurity_v5_8}$argon2id$v=19$m=16384,t=2,p=1$QUFBQUFBQUFBQUFBQUFBQQ$Pmbo2effrXPw4gygaMJwU4VdxCu7tjuARksTVxfFE9k",
+    ];
+
+    for stored_password in stored_passwords {
+        assert_eq!(encoder.try_matches("password", stored_password), Ok(true), "{}", stored_password);
+        assert_eq!(encoder.try_matches("wrongpassword", stored_password), Ok(false), "{}", stored_password);
+    }
+}
+
+#[test]
+fn crate_delegating_encode_spring_security_v5_8() {
+    let mut encoder: DelegatingPasswordEncoder = Default::default();
+    assert!(encoder.set_id_for_encode("pbkdf2@SpringSecurity_v5_8"));
+
+    let encoded_password = encoder.encode_spring_security_hash("Hello").unwrap();
+
+    assert!(encoded_password.starts_with("{pbkdf2@SpringSecurity_v5_8}"));
+    assert!(encoder.matches_spring_security_hash("Hello", &encoded_password));
+}
+
+#[test]
+fn crate_delegating_with_custom_id_markers() {
+    let encoder = DelegatingPasswordEncoder::builder()
+        .with_id_prefix("$$")
+        .with_id_suffix("$$")
+        .with_encoder("bcrypt", BCryptPasswordEncoder::new(BCryptVersion::TwoB, 4).unwrap())
+        .build()
+        .unwrap();
+
+    let encoded_password = encoder.encode_spring_security_hash("Hello").unwrap();
+
+    assert!(encoded_password.starts_with("$$bcrypt$$$2b$04$"));
+    assert!(encoder.matches_spring_security_hash("Hello", &encoded_password));
+}
+
+#[test]
+fn crate_delegating_matches_wrapped_legacy_md5() {
+    let encoder: DelegatingPasswordEncoder = Default::default();
+    let onion_encoder: BCryptMd5PasswordEncoder = Default::default();
+
+    let legacy_passwords = ["{MD5}{thisissalt}2a4e7104c2780098f50ed5a84bb2323d"];
+    let wrapped_passwords: Vec<String> = onion_encoder
+        .wrap_legacy_hashes(legacy_passwords.iter().map(|legacy_password| legacy_password.strip_prefix("{MD5}").unwrap()))
+        .into_iter()
+        .map(|wrapped_password| String::from("{bcrypt-md5}") + &wrapped_password.unwrap())
+        .collect();
+
+    assert!(wrapped_passwords[0].starts_with("{bcrypt-md5}{thisissalt}$2a$10$"));
+    assert_eq!(encoder.try_verify_and_upgrade("wrongpassword", &wrapped_passwords[0]), Ok(Verification::Mismatch));
+    match encoder.try_verify_and_upgrade("password", &wrapped_passwords[0]) {
+        Ok(Verification::Upgrade(upgraded_password)) => assert!(upgraded_password.starts_with("{bcrypt}")),
+        result => panic!("expected upgrade, got {:?}", result),
+    }
+}
@@ -0,0 +1,15 @@
+use password_encoder_for_spring_security_hashes::encoder::delegating::DelegatingPasswordEncoder;
+use password_encoder_for_spring_security_hashes::factory::EncoderAliases;
+use password_encoder_for_spring_security_hashes::{Encoder, PasswordEncoder};
+
+#[test]
+fn crate_factory_encoder_from_configured_name() {
+    let aliases = EncoderAliases::default().with_alias("legacy", Encoder::MD5);
+
+    let encoder = aliases.create_password_encoder("Legacy").unwrap();
+    let encoded_password = encoder.encode_spring_security_hash("Hello").unwrap();
+
+    let delegating: DelegatingPasswordEncoder = Default::default();
+
+    assert!(delegating.matches_spring_security_hash("Hello", &format!("{{{}}}{}", Encoder::MD5, encoded_password)));
+}
@@ -0,0 +1,16 @@
+use password_encoder_for_spring_security_hashes::encoder::delegating::DelegatingPasswordEncoder;
+use password_encoder_for_spring_security_hashes::info::{HashEncoding, HashInfo, HashParameters};
+use password_encoder_for_spring_security_hashes::{Encoder, PasswordEncoder};
+
+#[test]
+fn crate_info_of_default_encoded_password() {
+    let encoder: DelegatingPasswordEncoder = Default::default();
+
+    let encoded_password = encoder.encode_spring_security_hash("Hello").unwrap();
+    let info = HashInfo::parse(&encoded_password).unwrap();
+
+    assert_eq!(info.encoder, Encoder::BCRYPT);
+    assert!(matches!(info.parameters, HashParameters::BCrypt { cost: 10, .. }));
+    assert_eq!(info.salt.len(), 16);
+    assert_eq!(info.encoding, HashEncoding::BCryptBase64);
+}
@@ -0,0 +1,48 @@
+use password_encoder_for_spring_security_hashes::encoder::md4::Md4PasswordEncoder;
+use password_encoder_for_spring_security_hashes::encoder::md5::Md5PasswordEncoder;
+use password_encoder_for_spring_security_hashes::PasswordEncoder;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+// counts the allocations of the current thread, other tests run concurrently
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|allocations| allocations.set(allocations.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn allocations_during(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.with(Cell::get);
+    f();
+    ALLOCATIONS.with(Cell::get) - before
+}
+
+#[test]
+fn crate_md_matches_without_allocating() {
+    let md4_encoder = Md4PasswordEncoder::default();
+    let md5_encoder = Md5PasswordEncoder::default();
+    let base64_encoder = Md5PasswordEncoder::default().with_encode_hash_as_base64(true);
+
+    let allocations = allocations_during(|| {
+        assert!(md4_encoder.matches_spring_security_hash("password", "{thisissalt}6cc7924dad12ade79dfb99e424f25260"));
+        assert!(md5_encoder.matches_spring_security_hash("password", "{thisissalt}2a4e7104c2780098f50ed5a84bb2323d"));
+        assert!(!md5_encoder.matches_spring_security_hash("wrongpassword", "{thisissalt}2a4e7104c2780098f50ed5a84bb2323d"));
+        assert!(base64_encoder.matches_spring_security_hash("password", "{thisissalt}Kk5xBMJ4AJj1DtWoS7IyPQ=="));
+    });
+
+    assert_eq!(allocations, 0);
+}
@@ -0,0 +1,21 @@
+use password_encoder_for_spring_security_hashes::encoder::bcrypt::{BCryptPasswordEncoder, BCryptVersion};
+use password_encoder_for_spring_security_hashes::encoder::onion::OnionPasswordEncoder;
+use password_encoder_for_spring_security_hashes::encoder::sha1::Sha1PasswordEncoder;
+use password_encoder_for_spring_security_hashes::PasswordEncoder;
+
+#[test]
+fn crate_onion_wrap_legacy_table() {
+    let legacy_encoder = Sha1PasswordEncoder::default();
+    let encoder = OnionPasswordEncoder::new(Sha1PasswordEncoder::default(), BCryptPasswordEncoder::new(BCryptVersion::TwoB, 4).unwrap());
+
+    let passwords = ["Hello", "World"];
+    let legacy_table: Vec<String> = passwords.iter().map(|password| legacy_encoder.encode_spring_security_hash(password).unwrap()).collect();
+
+    let wrapped_table = encoder.wrap_legacy_hashes(&legacy_table);
+
+    for (password, wrapped_password) in passwords.iter().zip(wrapped_table) {
+        let wrapped_password = wrapped_password.unwrap();
+        assert!(encoder.matches_spring_security_hash(password, &wrapped_password));
+        assert!(!legacy_encoder.matches_spring_security_hash(password, &wrapped_password));
+    }
+}
@@ -0,0 +1,43 @@
+use password_encoder_for_spring_security_hashes::encoder::delegating::DelegatingPasswordEncoder;
+use password_encoder_for_spring_security_hashes::encoder::pepper::PepperedPasswordEncoder;
+use password_encoder_for_spring_security_hashes::password::SecretPassword;
+use password_encoder_for_spring_security_hashes::verification::Verification;
+use password_encoder_for_spring_security_hashes::{PasswordEncoder, PasswordEncoderExt};
+
+#[test]
+fn crate_pepper_delegating_rotation() {
+    let old_encoder = PepperedPasswordEncoder::new(DelegatingPasswordEncoder::default(), "1", SecretPassword::from("old key")).unwrap();
+    let encoder = PepperedPasswordEncoder::new(DelegatingPasswordEncoder::default(), "2", SecretPassword::from("new key"))
+        .unwrap()
+        .with_key("1", SecretPassword::from("old key"));
+
+    let stored_password = old_encoder.encode_spring_security_hash("Hello").unwrap();
+    assert!(stored_password.starts_with("$pepper$1${bcrypt}"));
+
+    match encoder.try_verify_and_upgrade("Hello", &stored_password) {
+        Ok(Verification::Upgrade(upgraded_password)) => {
+            assert!(upgraded_password.starts_with("$pepper$2${bcrypt}"));
+            assert_eq!(encoder.try_verify_and_upgrade("Hello", &upgraded_password), Ok(Verification::Match));
+            assert!(!old_encoder.matches_spring_security_hash("Hello", &upgraded_password));
+        }
+        result => panic!("expected upgrade, got {:?}", result),
+    }
+}
+
+#[test]
+fn crate_pepper_existing_hashes_on_login() {
+    let encoder = PepperedPasswordEncoder::new(DelegatingPasswordEncoder::default(), "1", SecretPassword::from("key"))
+        .unwrap()
+        .with_match_unpeppered(true);
+
+    let stored_password = "{MD5}{thisissalt}2a4e7104c2780098f50ed5a84bb2323d";
+
+    assert_eq!(encoder.try_verify_and_upgrade("wrongpassword", stored_password), Ok(Verification::Mismatch));
+    match encoder.try_verify_and_upgrade("password", stored_password) {
+        Ok(Verification::Upgrade(upgraded_password)) => {
+            assert!(upgraded_password.starts_with("$pepper$1${bcrypt}"));
+            assert_eq!(encoder.try_verify_and_upgrade("password", &upgraded_password), Ok(Verification::Match));
+        }
+        result => panic!("expected upgrade, got {:?}", result),
+    }
+}